@@ -0,0 +1,977 @@
+//! APEX motion functions
+//!
+//! The on-chip DMP runs the pedometer, tap detection, tilt detection and
+//! raise to wake on the accelerometer data, at the rate set by
+//! [`config::Apex`](crate::config::Apex). Enabling a function re-initializes
+//! the DMP, which restarts the step count of a running pedometer at 0.
+
+use crate::{
+    config::{AccelMode, AccelOdr, DmpOdr},
+    interrupt::{InterruptPin, Interrupts},
+    register_bank::{
+        bank0::{APEX_DATA0, APEX_DATA4},
+        Register, BANK0, BANK4,
+    },
+    Error, Ready, ICM42688,
+};
+
+#[cfg(feature = "async")]
+use crate::interface::AsyncRegisterInterface;
+
+#[cfg(feature = "blocking")]
+use crate::interface::RegisterInterface;
+
+/// Address of `APEX_DATA0`, followed by `APEX_DATA1` to `APEX_DATA3`
+const APEX_DATA0_ADDR: u8 = APEX_DATA0::ID;
+
+/// Address of `APEX_DATA4`, followed by `APEX_DATA5`
+const APEX_DATA4_ADDR: u8 = APEX_DATA4::ID;
+
+/// Pedometer tuning, `APEX_CONFIG1` to `APEX_CONFIG3` and `APEX_CONFIG9`
+///
+/// The defaults are the values recommended by the datasheet. The raw register
+/// values are used, see the datasheet for their meaning.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct PedometerParams {
+    /// `LOW_ENERGY_AMP_TH_SEL`, 4 bits
+    pub low_energy_amp_th: u8,
+    /// `DMP_POWER_SAVE_TIME_SEL`, 4 bits, in units of 4 s
+    pub power_save_time: u8,
+    /// `PED_AMP_TH_SEL`, 4 bits
+    pub amp_th: u8,
+    /// `PED_STEP_CNT_TH_SEL`, 4 bits: steps before the count starts
+    pub step_count_th: u8,
+    /// `PED_STEP_DET_TH_SEL`, 3 bits: steps before the count starts in walk
+    /// mode
+    pub step_detect_th: u8,
+    /// `PED_SB_TIMER_TH_SEL`, 3 bits: time without steps to leave walk mode
+    pub walk_timeout_th: u8,
+    /// `PED_HI_EN_TH_SEL`, 2 bits
+    pub high_energy_th: u8,
+    /// Improve detection of slow walks, `SENSITIVITY_MODE`
+    pub slow_walk: bool,
+}
+
+impl Default for PedometerParams {
+    fn default() -> Self {
+        Self {
+            low_energy_amp_th: 0b1010,
+            power_save_time: 0b0010,
+            amp_th: 0b1000,
+            step_count_th: 0b0101,
+            step_detect_th: 0b010,
+            walk_timeout_th: 0b100,
+            high_energy_th: 0b01,
+            slow_walk: false,
+        }
+    }
+}
+
+/// Activity detected by the pedometer, `ACTIVITY_CLASS` in `APEX_DATA3`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, defmt::Format)]
+pub enum ActivityClass {
+    Unknown,
+    Walk,
+    Run,
+}
+
+/// Pedometer output
+#[derive(Clone, Copy, Debug, PartialEq, defmt::Format)]
+pub struct Pedometer {
+    /// Steps since the pedometer was enabled
+    pub steps: u32,
+    /// Step frequency in Hz, 0 if no steps were detected yet
+    pub cadence_hz: f32,
+    pub activity: ActivityClass,
+}
+
+impl Pedometer {
+    /// Decode `APEX_DATA2` and `APEX_DATA3`, `steps` is the extended count
+    fn from_registers(steps: u32, data: [u8; 4], dmp_odr: DmpOdr) -> Self {
+        // Samples per step in u6.2 format
+        let cadence = data[2];
+        Pedometer {
+            steps,
+            cadence_hz: if cadence == 0 {
+                0.0
+            } else {
+                dmp_odr.hz() * 4.0 / cadence as f32
+            },
+            activity: match data[3] & 0b11 {
+                0b01 => ActivityClass::Walk,
+                0b10 => ActivityClass::Run,
+                _ => ActivityClass::Unknown,
+            },
+        }
+    }
+}
+
+/// Extends the 16-bit step count of the DMP
+///
+/// The count wraps around when `STEP_CNT_OVF_INT` is raised. The driver notes
+/// the flag whenever it reads `INT_STATUS3`, and the next update adds a wrap.
+/// If the flag was cleared without the driver seeing it, a count lower than
+/// the previous one is taken as a wrap instead. That fallback misses wraps if
+/// 65536 or more steps pass between two updates.
+#[derive(Clone, Copy, Default, Debug)]
+pub(crate) struct StepCounter {
+    last: u16,
+    overflows: u16,
+    /// `STEP_CNT_OVF_INT` was read since the last update
+    overflow_pending: bool,
+}
+
+impl StepCounter {
+    /// Note a `STEP_CNT_OVF_INT` flag read from `INT_STATUS3`
+    pub(crate) fn overflowed(&mut self) {
+        self.overflow_pending = true;
+    }
+
+    fn update(&mut self, raw: u16) -> u32 {
+        if core::mem::take(&mut self.overflow_pending) || raw < self.last {
+            self.overflows = self.overflows.wrapping_add(1);
+        }
+        self.last = raw;
+        ((self.overflows as u32) << 16) | raw as u32
+    }
+}
+
+/// Tap detection tuning, `APEX_CONFIG7` and `APEX_CONFIG8`
+///
+/// The defaults are the values recommended by the datasheet. The raw register
+/// values are used, see the datasheet for their meaning.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct TapParams {
+    /// `TAP_MIN_JERK_THR`, 6 bits
+    pub min_jerk_th: u8,
+    /// `TAP_MAX_PEAK_TOL`, 2 bits
+    pub max_peak_tol: u8,
+    /// `TAP_TMAX`, 2 bits
+    pub tmax: u8,
+    /// `TAP_TAVG`, 2 bits
+    pub tavg: u8,
+    /// `TAP_TMIN`, 3 bits
+    pub tmin: u8,
+}
+
+impl Default for TapParams {
+    fn default() -> Self {
+        Self {
+            min_jerk_th: 0b010001,
+            max_peak_tol: 0b01,
+            tmax: 0b01,
+            tavg: 0b01,
+            tmin: 0b011,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, defmt::Format)]
+pub enum TapCount {
+    Single,
+    Double,
+}
+
+/// Accelerometer axis the tap energy is concentrated on
+#[derive(Clone, Copy, Debug, PartialEq, Eq, defmt::Format)]
+pub enum TapAxis {
+    X,
+    Y,
+    Z,
+}
+
+/// Sign of the acceleration change of the tap
+#[derive(Clone, Copy, Debug, PartialEq, Eq, defmt::Format)]
+pub enum TapDirection {
+    Positive,
+    Negative,
+}
+
+/// Decoded `APEX_DATA4` and `APEX_DATA5`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, defmt::Format)]
+pub struct TapEvent {
+    pub count: TapCount,
+    pub axis: TapAxis,
+    pub direction: TapDirection,
+    /// Time between the two taps of a double tap in ms, resolution 16 samples
+    pub double_tap_interval_ms: Option<u16>,
+}
+
+impl TapEvent {
+    /// `None` if no tap was detected
+    fn from_registers(data: [u8; 2], odr_hz: u32) -> Option<Self> {
+        let count = match (data[0] >> 3) & 0b11 {
+            0b01 => TapCount::Single,
+            0b10 => TapCount::Double,
+            _ => return None,
+        };
+        let axis = match (data[0] >> 1) & 0b11 {
+            0b00 => TapAxis::X,
+            0b01 => TapAxis::Y,
+            0b10 => TapAxis::Z,
+            _ => return None,
+        };
+        let direction = if data[0] & 1 == 0 {
+            TapDirection::Positive
+        } else {
+            TapDirection::Negative
+        };
+        // Counted in units of 16 accel samples
+        let timing = (data[1] & 0b11_1111) as u32;
+        Some(TapEvent {
+            count,
+            axis,
+            direction,
+            double_tap_interval_ms: (count == TapCount::Double)
+                .then(|| (timing * 16 * 1000 / odr_hz) as u16),
+        })
+    }
+}
+
+/// Time the device has to stay tilted before the interrupt is raised,
+/// `TILT_WAIT_TIME_SEL` in `APEX_CONFIG4`
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum TiltWaitTime {
+    _0s = 0b00,
+    _2s = 0b01,
+    #[default]
+    _4s = 0b10,
+    _6s = 0b11,
+}
+
+/// A tilt of more than 35 degrees was held for the [`TiltWaitTime`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, defmt::Format)]
+pub struct TiltEvent;
+
+/// Raise to wake tuning, `APEX_CONFIG4` to `APEX_CONFIG6`
+///
+/// The defaults are the values recommended by the datasheet. The raw register
+/// values are used, see the datasheet for their meaning.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct RaiseToWakeParams {
+    /// `SLEEP_TIME_OUT`, 3 bits, in units of 1.28 s minus one
+    pub sleep_timeout: u8,
+    /// `SLEEP_GESTURE_DELAY`, 3 bits, in units of 0.32 s minus one
+    pub sleep_gesture_delay: u8,
+    /// `MOUNTING_MATRIX`, 3 bits: orientation of the chip in the device
+    pub mounting_matrix: u8,
+}
+
+impl Default for RaiseToWakeParams {
+    fn default() -> Self {
+        Self {
+            sleep_timeout: 0b100,
+            sleep_gesture_delay: 0b100,
+            mounting_matrix: 0b000,
+        }
+    }
+}
+
+/// Raise to wake gesture
+#[derive(Clone, Copy, Debug, PartialEq, Eq, defmt::Format)]
+pub enum WakeSleepEvent {
+    /// The device was raised
+    Wake,
+    /// The device was lowered, or not moved for the sleep timeout
+    Sleep,
+}
+
+impl Interrupts {
+    /// The tilt detection flag
+    pub fn tilt_event(self) -> Option<TiltEvent> {
+        self.tilt().then_some(TiltEvent)
+    }
+
+    /// The raise to wake flags
+    ///
+    /// If both flags are set, only [`WakeSleepEvent::Wake`] is returned.
+    pub fn wake_sleep_event(self) -> Option<WakeSleepEvent> {
+        if self.wake() {
+            Some(WakeSleepEvent::Wake)
+        } else if self.sleep() {
+            Some(WakeSleepEvent::Sleep)
+        } else {
+            None
+        }
+    }
+}
+
+/// Accel ODRs supported by tap detection, in Hz
+fn tap_odr_hz(odr: AccelOdr) -> Option<u32> {
+    match odr {
+        AccelOdr::_200Hz => Some(200),
+        AccelOdr::_500Hz => Some(500),
+        AccelOdr::_1kHz => Some(1000),
+        _ => None,
+    }
+}
+
+/// Accel mode used for tap detection at `odr_hz`
+fn tap_accel_mode(odr_hz: u32) -> AccelMode {
+    if odr_hz == 1000 {
+        AccelMode::LowNoise
+    } else {
+        AccelMode::LowPower
+    }
+}
+
+impl<BUS, PIN> ICM42688<BUS, Ready<PIN>> {
+    /// Enable the pedometer and route step detection to `pin`
+    ///
+    /// Switches the accelerometer to low power mode. Returns
+    /// [`Error::UnsupportedOdr`] if the accel ODR is not supported in low
+    /// power mode (above 500 Hz) or is below the
+    /// [DMP ODR](crate::config::Apex). The step count restarts at 0.
+    #[cfg(feature = "async")]
+    pub async fn async_enable_pedometer(
+        &mut self,
+        mut delay: impl embedded_hal_async::delay::DelayNs,
+        params: PedometerParams,
+        pin: InterruptPin,
+    ) -> Result<(), Error<BUS::Error>>
+    where
+        BUS: AsyncRegisterInterface,
+    {
+        self.async_prepare_dmp(&mut delay, AccelMode::LowPower)
+            .await?;
+
+        self.async_select_bank(BANK4).await?;
+        let mut bank4 = self.ll.try_bank::<4>()?;
+        bank4
+            .apex_config1()
+            .async_write(|w| {
+                w.low_energy_amp_th_sel(params.low_energy_amp_th)
+                    .dmp_power_save_time_sel(params.power_save_time)
+            })
+            .await?;
+        bank4
+            .apex_config2()
+            .async_write(|w| {
+                w.ped_amp_th_sel(params.amp_th)
+                    .ped_step_cnt_th_sel(params.step_count_th)
+            })
+            .await?;
+        bank4
+            .apex_config3()
+            .async_write(|w| {
+                w.ped_step_det_th_sel(params.step_detect_th)
+                    .ped_sb_timer_th_sel(params.walk_timeout_th)
+                    .ped_hi_en_th_sel(params.high_energy_th)
+            })
+            .await?;
+        bank4
+            .apex_config9()
+            .async_write(|w| w.sensitivity_mode(params.slow_walk as u8))
+            .await?;
+        self.async_select_bank(BANK0).await?;
+
+        self.async_init_dmp(&mut delay).await?;
+        self.async_enable_interrupts(pin, Interrupts::STEP | Interrupts::STEP_COUNT_OVERFLOW)
+            .await?;
+        self.ll
+            .try_bank::<0>()?
+            .apex_config0()
+            .async_modify(|w| w.ped_enable(1))
+            .await?;
+        Ok(())
+    }
+
+    /// Enable the pedometer and route step detection to `pin`
+    ///
+    /// Switches the accelerometer to low power mode. Returns
+    /// [`Error::UnsupportedOdr`] if the accel ODR is not supported in low
+    /// power mode (above 500 Hz) or is below the
+    /// [DMP ODR](crate::config::Apex). The step count restarts at 0.
+    #[cfg(feature = "blocking")]
+    pub fn enable_pedometer(
+        &mut self,
+        mut delay: impl embedded_hal::delay::DelayNs,
+        params: PedometerParams,
+        pin: InterruptPin,
+    ) -> Result<(), Error<BUS::Error>>
+    where
+        BUS: RegisterInterface,
+    {
+        self.prepare_dmp(&mut delay, AccelMode::LowPower)?;
+
+        self.select_bank(BANK4)?;
+        let mut bank4 = self.ll.try_bank::<4>()?;
+        bank4.apex_config1().write(|w| {
+            w.low_energy_amp_th_sel(params.low_energy_amp_th)
+                .dmp_power_save_time_sel(params.power_save_time)
+        })?;
+        bank4.apex_config2().write(|w| {
+            w.ped_amp_th_sel(params.amp_th)
+                .ped_step_cnt_th_sel(params.step_count_th)
+        })?;
+        bank4.apex_config3().write(|w| {
+            w.ped_step_det_th_sel(params.step_detect_th)
+                .ped_sb_timer_th_sel(params.walk_timeout_th)
+                .ped_hi_en_th_sel(params.high_energy_th)
+        })?;
+        bank4
+            .apex_config9()
+            .write(|w| w.sensitivity_mode(params.slow_walk as u8))?;
+        self.select_bank(BANK0)?;
+
+        self.init_dmp(&mut delay)?;
+        self.enable_interrupts(pin, Interrupts::STEP | Interrupts::STEP_COUNT_OVERFLOW)?;
+        self.ll
+            .try_bank::<0>()?
+            .apex_config0()
+            .modify(|_, w| w.ped_enable(1))?;
+        Ok(())
+    }
+
+    /// Read the step count, cadence and activity
+    ///
+    /// The 16-bit count of the device is extended on `STEP_CNT_OVF_INT`,
+    /// which the driver picks up when it reads the interrupt status (e.g.
+    /// [`read_interrupt_status`](ICM42688::read_interrupt_status)). Route
+    /// [`Interrupts::STEP_COUNT_OVERFLOW`] to a pin and handle its events, or
+    /// call this at least once every 65536 steps.
+    #[cfg(feature = "async")]
+    pub async fn async_read_pedometer(&mut self) -> Result<Pedometer, Error<BUS::Error>>
+    where
+        BUS: AsyncRegisterInterface,
+    {
+        self.ll.try_bank::<0>()?;
+        let mut data = [0; 4];
+        self.ll
+            .bus
+            .read_regs(APEX_DATA0_ADDR, &mut data)
+            .await
+            .map_err(Error::Bus)?;
+        let steps = self
+            .state
+            .inner
+            .step_counter
+            .update(u16::from_le_bytes([data[0], data[1]]));
+        Ok(Pedometer::from_registers(
+            steps,
+            data,
+            self.state.inner.dmp_odr,
+        ))
+    }
+
+    /// Read the step count, cadence and activity
+    ///
+    /// The 16-bit count of the device is extended on `STEP_CNT_OVF_INT`,
+    /// which the driver picks up when it reads the interrupt status (e.g.
+    /// [`read_interrupt_status`](ICM42688::read_interrupt_status)). Route
+    /// [`Interrupts::STEP_COUNT_OVERFLOW`] to a pin and handle its events, or
+    /// call this at least once every 65536 steps.
+    #[cfg(feature = "blocking")]
+    pub fn read_pedometer(&mut self) -> Result<Pedometer, Error<BUS::Error>>
+    where
+        BUS: RegisterInterface,
+    {
+        self.ll.try_bank::<0>()?;
+        let mut data = [0; 4];
+        self.ll
+            .bus
+            .read_regs(APEX_DATA0_ADDR, &mut data)
+            .map_err(Error::Bus)?;
+        let steps = self
+            .state
+            .inner
+            .step_counter
+            .update(u16::from_le_bytes([data[0], data[1]]));
+        Ok(Pedometer::from_registers(
+            steps,
+            data,
+            self.state.inner.dmp_odr,
+        ))
+    }
+
+    /// Enable tap detection and route it to `pin`
+    ///
+    /// The accel ODR has to be 200 Hz, 500 Hz or 1 kHz, otherwise
+    /// [`Error::UnsupportedOdr`] is returned. The accelerometer is switched to
+    /// low power mode, or low noise mode at 1 kHz, and its UI filter to 3rd
+    /// order.
+    #[cfg(feature = "async")]
+    pub async fn async_enable_tap_detection(
+        &mut self,
+        mut delay: impl embedded_hal_async::delay::DelayNs,
+        params: TapParams,
+        pin: InterruptPin,
+    ) -> Result<(), Error<BUS::Error>>
+    where
+        BUS: AsyncRegisterInterface,
+    {
+        let odr_hz = tap_odr_hz(self.state.inner.accel_odr).ok_or(Error::UnsupportedOdr)?;
+        self.async_prepare_dmp(&mut delay, tap_accel_mode(odr_hz))
+            .await?;
+        self.ll
+            .try_bank::<0>()?
+            .accel_config1()
+            .async_modify(|w| w.accel_dec2_m2_ord(0b10).accel_ui_filt_ord(0b10))
+            .await?;
+
+        self.async_select_bank(BANK4).await?;
+        let mut bank4 = self.ll.try_bank::<4>()?;
+        bank4
+            .apex_config7()
+            .async_write(|w| {
+                w.tap_min_jerk_thr(params.min_jerk_th)
+                    .tap_max_peak_tol(params.max_peak_tol)
+            })
+            .await?;
+        bank4
+            .apex_config8()
+            .async_write(|w| {
+                w.tap_tmax(params.tmax)
+                    .tap_tavg(params.tavg)
+                    .tap_tmin(params.tmin)
+            })
+            .await?;
+        self.async_select_bank(BANK0).await?;
+
+        self.async_init_dmp(&mut delay).await?;
+        self.async_enable_interrupts(pin, Interrupts::TAP).await?;
+        self.ll
+            .try_bank::<0>()?
+            .apex_config0()
+            .async_modify(|w| w.tap_enable(1))
+            .await?;
+        Ok(())
+    }
+
+    /// Enable tap detection and route it to `pin`
+    ///
+    /// The accel ODR has to be 200 Hz, 500 Hz or 1 kHz, otherwise
+    /// [`Error::UnsupportedOdr`] is returned. The accelerometer is switched to
+    /// low power mode, or low noise mode at 1 kHz, and its UI filter to 3rd
+    /// order.
+    #[cfg(feature = "blocking")]
+    pub fn enable_tap_detection(
+        &mut self,
+        mut delay: impl embedded_hal::delay::DelayNs,
+        params: TapParams,
+        pin: InterruptPin,
+    ) -> Result<(), Error<BUS::Error>>
+    where
+        BUS: RegisterInterface,
+    {
+        let odr_hz = tap_odr_hz(self.state.inner.accel_odr).ok_or(Error::UnsupportedOdr)?;
+        self.prepare_dmp(&mut delay, tap_accel_mode(odr_hz))?;
+        self.ll
+            .try_bank::<0>()?
+            .accel_config1()
+            .modify(|_, w| w.accel_dec2_m2_ord(0b10).accel_ui_filt_ord(0b10))?;
+
+        self.select_bank(BANK4)?;
+        let mut bank4 = self.ll.try_bank::<4>()?;
+        bank4.apex_config7().write(|w| {
+            w.tap_min_jerk_thr(params.min_jerk_th)
+                .tap_max_peak_tol(params.max_peak_tol)
+        })?;
+        bank4.apex_config8().write(|w| {
+            w.tap_tmax(params.tmax)
+                .tap_tavg(params.tavg)
+                .tap_tmin(params.tmin)
+        })?;
+        self.select_bank(BANK0)?;
+
+        self.init_dmp(&mut delay)?;
+        self.enable_interrupts(pin, Interrupts::TAP)?;
+        self.ll
+            .try_bank::<0>()?
+            .apex_config0()
+            .modify(|_, w| w.tap_enable(1))?;
+        Ok(())
+    }
+
+    /// Read the latest tap, `None` if no tap was detected
+    ///
+    /// The double tap interval is converted with the configured accel ODR.
+    /// Returns [`Error::UnsupportedOdr`] if tap detection doesn't support that
+    /// ODR, even if tap detection was never enabled.
+    #[cfg(feature = "async")]
+    pub async fn async_read_tap(&mut self) -> Result<Option<TapEvent>, Error<BUS::Error>>
+    where
+        BUS: AsyncRegisterInterface,
+    {
+        let odr_hz = tap_odr_hz(self.state.inner.accel_odr).ok_or(Error::UnsupportedOdr)?;
+        self.ll.try_bank::<0>()?;
+        let mut data = [0; 2];
+        self.ll
+            .bus
+            .read_regs(APEX_DATA4_ADDR, &mut data)
+            .await
+            .map_err(Error::Bus)?;
+        Ok(TapEvent::from_registers(data, odr_hz))
+    }
+
+    /// Read the latest tap, `None` if no tap was detected
+    ///
+    /// The double tap interval is converted with the configured accel ODR.
+    /// Returns [`Error::UnsupportedOdr`] if tap detection doesn't support that
+    /// ODR, even if tap detection was never enabled.
+    #[cfg(feature = "blocking")]
+    pub fn read_tap(&mut self) -> Result<Option<TapEvent>, Error<BUS::Error>>
+    where
+        BUS: RegisterInterface,
+    {
+        let odr_hz = tap_odr_hz(self.state.inner.accel_odr).ok_or(Error::UnsupportedOdr)?;
+        self.ll.try_bank::<0>()?;
+        let mut data = [0; 2];
+        self.ll
+            .bus
+            .read_regs(APEX_DATA4_ADDR, &mut data)
+            .map_err(Error::Bus)?;
+        Ok(TapEvent::from_registers(data, odr_hz))
+    }
+
+    /// Enable tilt detection and route it to `pin`
+    ///
+    /// Switches the accelerometer to low power mode. Returns
+    /// [`Error::UnsupportedOdr`] if the accel ODR is not supported in low
+    /// power mode (above 500 Hz) or is below the
+    /// [DMP ODR](crate::config::Apex).
+    #[cfg(feature = "async")]
+    pub async fn async_enable_tilt_detection(
+        &mut self,
+        mut delay: impl embedded_hal_async::delay::DelayNs,
+        wait_time: TiltWaitTime,
+        pin: InterruptPin,
+    ) -> Result<(), Error<BUS::Error>>
+    where
+        BUS: AsyncRegisterInterface,
+    {
+        self.async_prepare_dmp(&mut delay, AccelMode::LowPower)
+            .await?;
+
+        self.async_select_bank(BANK4).await?;
+        self.ll
+            .try_bank::<4>()?
+            .apex_config4()
+            .async_modify(|w| w.tilt_wait_time_sel(wait_time as u8))
+            .await?;
+        self.async_select_bank(BANK0).await?;
+
+        self.async_init_dmp(&mut delay).await?;
+        self.async_enable_interrupts(pin, Interrupts::TILT).await?;
+        self.ll
+            .try_bank::<0>()?
+            .apex_config0()
+            .async_modify(|w| w.tilt_enable(1))
+            .await?;
+        Ok(())
+    }
+
+    /// Enable tilt detection and route it to `pin`
+    ///
+    /// Switches the accelerometer to low power mode. Returns
+    /// [`Error::UnsupportedOdr`] if the accel ODR is not supported in low
+    /// power mode (above 500 Hz) or is below the
+    /// [DMP ODR](crate::config::Apex).
+    #[cfg(feature = "blocking")]
+    pub fn enable_tilt_detection(
+        &mut self,
+        mut delay: impl embedded_hal::delay::DelayNs,
+        wait_time: TiltWaitTime,
+        pin: InterruptPin,
+    ) -> Result<(), Error<BUS::Error>>
+    where
+        BUS: RegisterInterface,
+    {
+        self.prepare_dmp(&mut delay, AccelMode::LowPower)?;
+
+        self.select_bank(BANK4)?;
+        self.ll
+            .try_bank::<4>()?
+            .apex_config4()
+            .modify(|_, w| w.tilt_wait_time_sel(wait_time as u8))?;
+        self.select_bank(BANK0)?;
+
+        self.init_dmp(&mut delay)?;
+        self.enable_interrupts(pin, Interrupts::TILT)?;
+        self.ll
+            .try_bank::<0>()?
+            .apex_config0()
+            .modify(|_, w| w.tilt_enable(1))?;
+        Ok(())
+    }
+
+    /// Enable raise to wake and route the wake and sleep events to `pin`
+    ///
+    /// Switches the accelerometer to low power mode. Returns
+    /// [`Error::UnsupportedOdr`] if the accel ODR is not supported in low
+    /// power mode (above 500 Hz) or is below the
+    /// [DMP ODR](crate::config::Apex).
+    #[cfg(feature = "async")]
+    pub async fn async_enable_raise_to_wake(
+        &mut self,
+        mut delay: impl embedded_hal_async::delay::DelayNs,
+        params: RaiseToWakeParams,
+        pin: InterruptPin,
+    ) -> Result<(), Error<BUS::Error>>
+    where
+        BUS: AsyncRegisterInterface,
+    {
+        self.async_prepare_dmp(&mut delay, AccelMode::LowPower)
+            .await?;
+
+        self.async_select_bank(BANK4).await?;
+        let mut bank4 = self.ll.try_bank::<4>()?;
+        bank4
+            .apex_config4()
+            .async_modify(|w| w.sleep_time_out(params.sleep_timeout))
+            .await?;
+        bank4
+            .apex_config5()
+            .async_modify(|w| w.mounting_matrix(params.mounting_matrix))
+            .await?;
+        bank4
+            .apex_config6()
+            .async_modify(|w| w.sleep_gesture_delay(params.sleep_gesture_delay))
+            .await?;
+        self.async_select_bank(BANK0).await?;
+
+        self.async_init_dmp(&mut delay).await?;
+        self.async_enable_interrupts(pin, Interrupts::WAKE | Interrupts::SLEEP)
+            .await?;
+        self.ll
+            .try_bank::<0>()?
+            .apex_config0()
+            .async_modify(|w| w.r2w_en(1))
+            .await?;
+        Ok(())
+    }
+
+    /// Enable raise to wake and route the wake and sleep events to `pin`
+    ///
+    /// Switches the accelerometer to low power mode. Returns
+    /// [`Error::UnsupportedOdr`] if the accel ODR is not supported in low
+    /// power mode (above 500 Hz) or is below the
+    /// [DMP ODR](crate::config::Apex).
+    #[cfg(feature = "blocking")]
+    pub fn enable_raise_to_wake(
+        &mut self,
+        mut delay: impl embedded_hal::delay::DelayNs,
+        params: RaiseToWakeParams,
+        pin: InterruptPin,
+    ) -> Result<(), Error<BUS::Error>>
+    where
+        BUS: RegisterInterface,
+    {
+        self.prepare_dmp(&mut delay, AccelMode::LowPower)?;
+
+        self.select_bank(BANK4)?;
+        let mut bank4 = self.ll.try_bank::<4>()?;
+        bank4
+            .apex_config4()
+            .modify(|_, w| w.sleep_time_out(params.sleep_timeout))?;
+        bank4
+            .apex_config5()
+            .modify(|_, w| w.mounting_matrix(params.mounting_matrix))?;
+        bank4
+            .apex_config6()
+            .modify(|_, w| w.sleep_gesture_delay(params.sleep_gesture_delay))?;
+        self.select_bank(BANK0)?;
+
+        self.init_dmp(&mut delay)?;
+        self.enable_interrupts(pin, Interrupts::WAKE | Interrupts::SLEEP)?;
+        self.ll
+            .try_bank::<0>()?
+            .apex_config0()
+            .modify(|_, w| w.r2w_en(1))?;
+        Ok(())
+    }
+
+    /// Set the accelerometer mode and the DMP ODR
+    ///
+    /// Returns [`Error::UnsupportedOdr`] without touching the device if the
+    /// accel ODR is below the DMP ODR or not available in `accel_mode`.
+    #[cfg(feature = "async")]
+    async fn async_prepare_dmp(
+        &mut self,
+        delay: &mut impl embedded_hal_async::delay::DelayNs,
+        accel_mode: AccelMode,
+    ) -> Result<(), Error<BUS::Error>>
+    where
+        BUS: AsyncRegisterInterface,
+    {
+        let dmp_odr = self.check_dmp_odr()?;
+        match accel_mode {
+            AccelMode::LowPower => self.async_accel_low_power().await?,
+            _ => {
+                self.ll
+                    .try_bank::<0>()?
+                    .pwr_mgmt0()
+                    .async_modify(|w| w.accel_mode(accel_mode as u8))
+                    .await?;
+            }
+        }
+        self.ll
+            .try_bank::<0>()?
+            .apex_config0()
+            .async_modify(|w| w.dmp_odr(dmp_odr as u8))
+            .await?;
+        delay.delay_ms(1).await;
+        Ok(())
+    }
+
+    /// Set the accelerometer mode and the DMP ODR
+    ///
+    /// Returns [`Error::UnsupportedOdr`] without touching the device if the
+    /// accel ODR is below the DMP ODR or not available in `accel_mode`.
+    #[cfg(feature = "blocking")]
+    fn prepare_dmp(
+        &mut self,
+        delay: &mut impl embedded_hal::delay::DelayNs,
+        accel_mode: AccelMode,
+    ) -> Result<(), Error<BUS::Error>>
+    where
+        BUS: RegisterInterface,
+    {
+        let dmp_odr = self.check_dmp_odr()?;
+        match accel_mode {
+            AccelMode::LowPower => self.accel_low_power()?,
+            _ => {
+                self.ll
+                    .try_bank::<0>()?
+                    .pwr_mgmt0()
+                    .modify(|_, w| w.accel_mode(accel_mode as u8))?;
+            }
+        }
+        self.ll
+            .try_bank::<0>()?
+            .apex_config0()
+            .modify(|_, w| w.dmp_odr(dmp_odr as u8))?;
+        delay.delay_ms(1);
+        Ok(())
+    }
+
+    /// The DMP ODR, if the accel data is fast enough for it
+    fn check_dmp_odr<E>(&self) -> Result<DmpOdr, Error<E>> {
+        let dmp_odr = self.state.inner.dmp_odr;
+        if self.state.inner.accel_odr.hz() < dmp_odr.hz() {
+            return Err(Error::UnsupportedOdr);
+        }
+        Ok(dmp_odr)
+    }
+
+    /// Reset the DMP memory and start the DMP
+    ///
+    /// The memory reset clears the step count of the device, so the extended
+    /// count restarts as well.
+    #[cfg(feature = "async")]
+    async fn async_init_dmp(
+        &mut self,
+        delay: &mut impl embedded_hal_async::delay::DelayNs,
+    ) -> Result<(), Error<BUS::Error>>
+    where
+        BUS: AsyncRegisterInterface,
+    {
+        let mut bank0 = self.ll.try_bank::<0>()?;
+        bank0
+            .signal_path_reset()
+            .async_modify(|w| w.dmp_mem_reset_en(1))
+            .await?;
+        self.state.inner.step_counter = StepCounter::default();
+        delay.delay_ms(1).await;
+        bank0
+            .signal_path_reset()
+            .async_modify(|w| w.dmp_init_en(1))
+            .await?;
+        delay.delay_ms(50).await;
+        Ok(())
+    }
+
+    /// Reset the DMP memory and start the DMP
+    ///
+    /// The memory reset clears the step count of the device, so the extended
+    /// count restarts as well.
+    #[cfg(feature = "blocking")]
+    fn init_dmp(
+        &mut self,
+        delay: &mut impl embedded_hal::delay::DelayNs,
+    ) -> Result<(), Error<BUS::Error>>
+    where
+        BUS: RegisterInterface,
+    {
+        let mut bank0 = self.ll.try_bank::<0>()?;
+        bank0
+            .signal_path_reset()
+            .modify(|_, w| w.dmp_mem_reset_en(1))?;
+        self.state.inner.step_counter = StepCounter::default();
+        delay.delay_ms(1);
+        bank0.signal_path_reset().modify(|_, w| w.dmp_init_en(1))?;
+        delay.delay_ms(50);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_step_counter() {
+        let mut counter = StepCounter::default();
+        assert_eq!(counter.update(10), 10);
+        assert_eq!(counter.update(65_530), 65_530);
+        assert_eq!(counter.update(4), 65_540);
+        assert_eq!(counter.update(4), 65_540);
+    }
+
+    #[test]
+    fn test_step_counter_overflow_flag() {
+        let mut counter = StepCounter::default();
+        assert_eq!(counter.update(100), 100);
+        // More than 65536 steps between the updates
+        counter.overflowed();
+        assert_eq!(counter.update(200), 65_736);
+        // The flag and the lower count are the same wrap
+        counter.overflowed();
+        assert_eq!(counter.update(50), 131_122);
+        assert_eq!(counter.update(60), 131_132);
+    }
+
+    #[test]
+    fn test_pedometer_registers() {
+        // 2 Hz walk at 50 Hz: 25 samples per step
+        let pedometer = Pedometer::from_registers(7, [7, 0, 100, 0b01], DmpOdr::_50Hz);
+        assert_eq!(pedometer.cadence_hz, 2.0);
+        assert_eq!(pedometer.activity, ActivityClass::Walk);
+        assert_eq!(
+            Pedometer::from_registers(0, [0; 4], DmpOdr::_25Hz).cadence_hz,
+            0.0
+        );
+    }
+
+    #[test]
+    fn test_tap_registers() {
+        assert_eq!(TapEvent::from_registers([0, 0], 1000), None);
+        // Double tap on Z, negative, 4 * 16 samples apart at 500 Hz
+        let tap = TapEvent::from_registers([0b1_0101, 4], 500).unwrap();
+        assert_eq!(tap.count, TapCount::Double);
+        assert_eq!(tap.axis, TapAxis::Z);
+        assert_eq!(tap.direction, TapDirection::Negative);
+        assert_eq!(tap.double_tap_interval_ms, Some(128));
+        let tap = TapEvent::from_registers([0b0_1000, 4], 200).unwrap();
+        assert_eq!(tap.count, TapCount::Single);
+        assert_eq!(tap.double_tap_interval_ms, None);
+    }
+    #[test]
+    fn test_wake_sleep_event() {
+        assert_eq!(Interrupts::empty().wake_sleep_event(), None);
+        assert_eq!(Interrupts::empty().tilt_event(), None);
+        assert_eq!(Interrupts::TILT.tilt_event(), Some(TiltEvent));
+        assert_eq!(
+            Interrupts::SLEEP.wake_sleep_event(),
+            Some(WakeSleepEvent::Sleep)
+        );
+        assert_eq!(
+            (Interrupts::WAKE | Interrupts::SLEEP).wake_sleep_event(),
+            Some(WakeSleepEvent::Wake)
+        );
+    }
+}
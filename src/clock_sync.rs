@@ -0,0 +1,181 @@
+//! Mapping of the sensor timeline to host time
+//!
+//! The sensor runs from its own RC oscillator (or PLL), which can drift by up
+//! to ±1% relative to the host clock. [`ClockSync`] estimates the offset and
+//! skew between the two from pairs of timestamps and maps sensor times (as
+//! produced by a [`TimestampTracker`](crate::timestamp::TimestampTracker)) to
+//! host time.
+//!
+//! A good source of pairs is the INT1 watermark interrupt: capture the host
+//! time in the interrupt handler, then read the FIFO and pair it with the
+//! sensor time of the newest packet.
+
+/// Recursive linear fit of host time over sensor time
+///
+/// Uses exponentially weighted least squares, so old pairs are gradually
+/// forgotten and a changing drift is followed. The fit works on the µs
+/// timelines only, so ODR changes don't affect it.
+///
+/// A pair that is further than `max_residual_us` off the current estimate
+/// (e.g. because samples were lost when the FIFO was reset or overflowed)
+/// restarts the fit at that pair. The skew estimate is kept, so the mapping
+/// stays stable across such jumps.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ClockSync {
+    forgetting_factor: f64,
+    max_residual_us: f64,
+    /// First pair, all other values are relative to it
+    origin: Option<(u64, u64)>,
+    /// Sum of the weights
+    weight: f64,
+    mean_sensor: f64,
+    mean_host: f64,
+    /// Weighted (co)variances, not normalized
+    var_sensor: f64,
+    cov: f64,
+    skew: f64,
+}
+
+impl Default for ClockSync {
+    fn default() -> Self {
+        Self::new(0.99, 5_000)
+    }
+}
+
+impl ClockSync {
+    /// Create a new estimator
+    ///
+    /// `forgetting_factor` in (0, 1] sets how fast old pairs are forgotten,
+    /// 1 weighs all pairs equally. `max_residual_us` is the largest deviation
+    /// from the estimate that is treated as jitter rather than a jump.
+    pub fn new(forgetting_factor: f64, max_residual_us: u32) -> Self {
+        ClockSync {
+            forgetting_factor,
+            max_residual_us: max_residual_us as f64,
+            origin: None,
+            weight: 0.0,
+            mean_sensor: 0.0,
+            mean_host: 0.0,
+            var_sensor: 0.0,
+            cov: 0.0,
+            skew: 1.0,
+        }
+    }
+
+    /// Forget everything, including the skew estimate
+    pub fn reset(&mut self) {
+        *self = Self::new(self.forgetting_factor, self.max_residual_us as u32);
+    }
+
+    /// Add a pair of sensor and host timestamps, both in µs
+    pub fn update(&mut self, sensor_us: u64, host_us: u64) {
+        let (x, y) = match self.origin {
+            Some(origin) => relative(origin, sensor_us, host_us),
+            None => {
+                self.origin = Some((sensor_us, host_us));
+                (0.0, 0.0)
+            }
+        };
+
+        if self.weight > 0.0 {
+            let residual = y - self.predict(x);
+            if residual.abs() > self.max_residual_us {
+                self.restart(sensor_us, host_us);
+                return;
+            }
+        }
+
+        let lambda = self.forgetting_factor;
+        self.weight = lambda * self.weight + 1.0;
+        let dx = x - self.mean_sensor;
+        self.mean_sensor += dx / self.weight;
+        self.mean_host += (y - self.mean_host) / self.weight;
+        self.var_sensor = lambda * self.var_sensor + dx * (x - self.mean_sensor);
+        self.cov = lambda * self.cov + dx * (y - self.mean_host);
+
+        // Wait for enough spread before trusting the slope
+        if self.var_sensor > 1.0 {
+            self.skew = self.cov / self.var_sensor;
+        }
+    }
+
+    /// Map a sensor time to host time, both in µs
+    ///
+    /// Returns `None` until the first pair was added.
+    pub fn to_host(&self, sensor_us: u64) -> Option<u64> {
+        let origin = self.origin?;
+        let x = sensor_us as f64 - origin.0 as f64;
+        let host = origin.1 as f64 + self.predict(x);
+        Some(if host < 0.0 { 0 } else { host as u64 })
+    }
+
+    /// Estimated host µs per sensor µs
+    pub fn skew(&self) -> f64 {
+        self.skew
+    }
+
+    fn predict(&self, x: f64) -> f64 {
+        self.mean_host + self.skew * (x - self.mean_sensor)
+    }
+
+    /// Restart the fit at a pair, keeping the skew
+    fn restart(&mut self, sensor_us: u64, host_us: u64) {
+        let skew = self.skew;
+        self.reset();
+        self.skew = skew;
+        self.update(sensor_us, host_us);
+    }
+}
+
+fn relative(origin: (u64, u64), sensor_us: u64, host_us: u64) -> (f64, f64) {
+    (
+        sensor_us as f64 - origin.0 as f64,
+        host_us as f64 - origin.1 as f64,
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Host clock running 1% faster than the sensor, 1 s apart
+    fn host_time(sensor_us: u64) -> u64 {
+        1_000_000 + sensor_us * 101 / 100
+    }
+
+    #[test]
+    fn test_skew_and_offset() {
+        let mut sync = ClockSync::default();
+        assert_eq!(sync.to_host(0), None);
+
+        for i in 0..20 {
+            let sensor = 5_000 + i * 10_000;
+            // +-3 µs of interrupt latency jitter
+            let jitter = [0, 3, 1, 2][i as usize % 4];
+            sync.update(sensor, host_time(sensor) + jitter);
+        }
+
+        assert!((sync.skew() - 1.01).abs() < 1e-4);
+        let mapped = sync.to_host(300_000).unwrap() as i64;
+        assert!((mapped - host_time(300_000) as i64).abs() <= 5);
+    }
+
+    #[test]
+    fn test_jump() {
+        let mut sync = ClockSync::default();
+        for i in 0..10 {
+            let sensor = i * 10_000;
+            sync.update(sensor, host_time(sensor));
+        }
+
+        // The FIFO was reset, 50 ms of sensor time are missing
+        for i in 10..12 {
+            let sensor = i * 10_000;
+            sync.update(sensor, host_time(sensor) + 50_000);
+        }
+
+        assert!((sync.skew() - 1.01).abs() < 1e-4);
+        let mapped = sync.to_host(150_000).unwrap() as i64;
+        assert!((mapped - (host_time(150_000) + 50_000) as i64).abs() <= 2);
+    }
+}
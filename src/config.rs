@@ -4,8 +4,17 @@ pub struct Config {
     pub gyro: Gyro,
     pub accel: Accel,
     pub int1: Int1,
+    /// Only used if pin 9 is configured as [`Pin9Function::INT2`]
+    pub int2: Int2,
     pub pin9: Pin9,
-    /// Threshold in bytes for when to trigger int1
+    /// Only used if pin 9 is configured as [`Pin9Function::FSYNC`]
+    pub fsync: Fsync,
+    pub data_mode: DataMode,
+    pub fifo: Fifo,
+    pub timestamp: Timestamp,
+    pub apex: Apex,
+    /// Threshold for when to trigger int1, in bytes or in records if
+    /// `fifo.count_records` is set
     pub fifo_watermark: u16,
 }
 
@@ -13,6 +22,53 @@ pub struct Config {
 #[non_exhaustive]
 pub struct Gyro {
     pub odr: GyroOdr,
+    pub full_scale: GyroFullScale,
+}
+
+/// Gyroscope full-scale range, `GYRO_FS_SEL` in `GYRO_CONFIG0`
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum GyroFullScale {
+    /// ±2000 dps
+    #[default]
+    _2000Dps = 0b000,
+    /// ±1000 dps
+    _1000Dps = 0b001,
+    /// ±500 dps
+    _500Dps = 0b010,
+    /// ±250 dps
+    _250Dps = 0b011,
+    /// ±125 dps
+    _125Dps = 0b100,
+    /// ±62.5 dps
+    _62_5Dps = 0b101,
+    /// ±31.25 dps
+    _31_25Dps = 0b110,
+    /// ±15.625 dps
+    _15_625Dps = 0b111,
+}
+
+impl GyroFullScale {
+    /// Sensitivity of 16-bit data in LSB/dps
+    pub fn sensitivity(self) -> f32 {
+        self.lsb_per_dps_x10() as f32 / 10.0
+    }
+
+    /// Sensitivity of 16-bit data in LSB/dps, times 10
+    ///
+    /// Values of the sensitivity table in Section 3.1 of the datasheet.
+    pub(crate) fn lsb_per_dps_x10(self) -> i64 {
+        match self {
+            GyroFullScale::_2000Dps => 164,
+            GyroFullScale::_1000Dps => 328,
+            GyroFullScale::_500Dps => 655,
+            GyroFullScale::_250Dps => 1310,
+            GyroFullScale::_125Dps => 2620,
+            GyroFullScale::_62_5Dps => 5243,
+            GyroFullScale::_31_25Dps => 10_486,
+            GyroFullScale::_15_625Dps => 20_972,
+        }
+    }
 }
 
 #[derive(Clone, Copy, Default, Debug)]
@@ -50,6 +106,41 @@ pub enum GyroOdr {
 pub struct Accel {
     pub odr: AccelOdr,
     pub mode: AccelMode,
+    pub full_scale: AccelFullScale,
+}
+
+/// Accelerometer full-scale range, `ACCEL_FS_SEL` in `ACCEL_CONFIG0`
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum AccelFullScale {
+    /// ±16 g
+    #[default]
+    _16G = 0b000,
+    /// ±8 g
+    _8G = 0b001,
+    /// ±4 g
+    _4G = 0b010,
+    /// ±2 g
+    _2G = 0b011,
+}
+
+impl AccelFullScale {
+    /// Sensitivity of 16-bit data in LSB/g
+    pub fn sensitivity(self) -> f32 {
+        self.lsb_per_g_x10() as f32 / 10.0
+    }
+
+    /// Sensitivity of 16-bit data in LSB/g, times 10
+    ///
+    /// Values of the sensitivity table in Section 3.2 of the datasheet.
+    pub(crate) fn lsb_per_g_x10(self) -> i64 {
+        match self {
+            AccelFullScale::_16G => 20_480,
+            AccelFullScale::_8G => 40_960,
+            AccelFullScale::_4G => 81_920,
+            AccelFullScale::_2G => 163_840,
+        }
+    }
 }
 
 #[derive(Clone, Copy, Default, Debug)]
@@ -88,6 +179,33 @@ pub enum AccelOdr {
     _500Hz = 0b1111,
 }
 
+impl AccelOdr {
+    pub fn hz(self) -> f32 {
+        match self {
+            AccelOdr::_32kHz => 32_000.0,
+            AccelOdr::_16kHz => 16_000.0,
+            AccelOdr::_8kHz => 8_000.0,
+            AccelOdr::_4kHz => 4_000.0,
+            AccelOdr::_2kHz => 2_000.0,
+            AccelOdr::_1kHz => 1_000.0,
+            AccelOdr::_500Hz => 500.0,
+            AccelOdr::_200Hz => 200.0,
+            AccelOdr::_100Hz => 100.0,
+            AccelOdr::_50Hz => 50.0,
+            AccelOdr::_25Hz => 25.0,
+            AccelOdr::_12_5Hz => 12.5,
+            AccelOdr::_6_25Hz => 6.25,
+            AccelOdr::_3_125Hz => 3.125,
+            AccelOdr::_1_5625Hz => 1.5625,
+        }
+    }
+
+    /// The ODR is available in [`AccelMode::LowPower`]
+    pub fn supports_low_power(self) -> bool {
+        self.hz() <= 500.0
+    }
+}
+
 #[derive(Clone, Copy, Default, Debug)]
 #[repr(u8)]
 pub enum AccelMode {
@@ -97,22 +215,163 @@ pub enum AccelMode {
     LowNoise = 0b11,
 }
 
+/// How new data is signaled on INT1 and read
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq)]
+pub enum DataMode {
+    /// Data is collected in the FIFO, INT1 fires when the FIFO reaches
+    /// `fifo_watermark`
+    #[default]
+    FifoWatermark,
+    /// INT1 fires for every new sample, which is read from the data registers
+    /// (e.g. with `read_all`). The FIFO is bypassed, FIFO reads return
+    /// [`Error::WrongDataMode`](crate::Error::WrongDataMode).
+    DataReady,
+}
+
+/// Data written to the FIFO
+///
+/// The packet structure follows from these settings: packet 4 (20 bytes) with
+/// `high_resolution`, otherwise packet 3 (16 bytes) for accel and gyro, packet 1
+/// (8 bytes) for accel only and packet 2 (8 bytes) for gyro only. Temperature is
+/// always included.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Fifo {
+    /// Write accel data to the FIFO
+    pub accel: bool,
+    /// Write gyro data to the FIFO
+    pub gyro: bool,
+    /// Use 20-bit accel and gyro data
+    ///
+    /// The high resolution data always uses the ±16 g and ±2000 dps ranges,
+    /// regardless of the configured full-scale ranges.
+    pub high_resolution: bool,
+    pub mode: FifoMode,
+    /// Report the FIFO count and set the watermark in records (packets)
+    /// instead of bytes
+    pub count_records: bool,
+}
+
+impl Default for Fifo {
+    fn default() -> Self {
+        Self {
+            accel: true,
+            gyro: true,
+            high_resolution: true,
+            mode: FifoMode::default(),
+            count_records: false,
+        }
+    }
+}
+
+impl Fifo {
+    /// Size in bytes of the packets written to the FIFO
+    pub fn packet_size(&self) -> usize {
+        match (self.high_resolution, self.accel, self.gyro) {
+            (true, _, _) => 20,
+            (false, true, true) => 16,
+            _ => 8,
+        }
+    }
+
+    /// Convert a FIFO count as reported by the device to bytes
+    pub fn count_to_bytes(&self, count: u16) -> usize {
+        if self.count_records {
+            count as usize * self.packet_size()
+        } else {
+            count as usize
+        }
+    }
+
+    /// Convert a FIFO count as reported by the device to packets
+    pub fn count_to_packets(&self, count: u16) -> usize {
+        if self.count_records {
+            count as usize
+        } else {
+            count as usize / self.packet_size()
+        }
+    }
+}
+
+/// Behavior of the FIFO, `FIFO_MODE` in `FIFO_CONFIG`
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum FifoMode {
+    /// The FIFO is disabled
+    Bypass = 0b00,
+    /// Overwrite the oldest data when the FIFO is full
+    Stream = 0b01,
+    /// Stop writing to the FIFO when it is full
+    #[default]
+    StopOnFull = 0b11,
+}
+
+/// Timestamps written to the FIFO
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Timestamp {
+    /// Timestamps hold the time since the previous ODR instead of a running
+    /// counter
+    pub delta: bool,
+    pub resolution: TimestampResolution,
+}
+
+impl Default for Timestamp {
+    fn default() -> Self {
+        Self {
+            delta: true,
+            resolution: TimestampResolution::default(),
+        }
+    }
+}
+
+/// `TMST_RES` in `TMST_CONFIG`
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum TimestampResolution {
+    /// 1 µs
+    Fine = 0,
+    /// 16 µs, or one CLKIN period if pin 9 is used as CLKIN
+    #[default]
+    Coarse = 1,
+}
+
+/// Electrical configuration of an interrupt pin
+///
+/// The sources routed to the pins are selected with
+/// [`enable_interrupts`](crate::ICM42688::enable_interrupts).
 #[derive(Clone, Copy, Debug)]
 #[non_exhaustive]
-pub struct Int1 {
+pub struct IntPin {
     pub drive: Drive,
     pub polarity: Polarity,
+    pub mode: IntMode,
 }
 
-impl Default for Int1 {
+impl Default for IntPin {
     fn default() -> Self {
         Self {
             drive: Drive::PushPull,
             polarity: Polarity::ActiveLow,
+            mode: IntMode::Latched,
         }
     }
 }
 
+pub type Int1 = IntPin;
+pub type Int2 = IntPin;
+
+/// `INTx_MODE` in `INT_CONFIG`
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum IntMode {
+    /// The pin is asserted for a short pulse
+    Pulsed = 0,
+    /// The pin stays asserted until the status is cleared
+    #[default]
+    Latched = 1,
+}
+
 #[derive(Clone, Copy, Debug)]
 #[repr(u8)]
 pub enum Drive {
@@ -127,10 +386,22 @@ pub enum Polarity {
     ActiveHigh = 1,
 }
 
-#[derive(Clone, Copy, Default, Debug)]
+#[derive(Clone, Copy, Debug)]
 #[non_exhaustive]
 pub struct Pin9 {
     pub function: Pin9Function,
+    /// Frequency of the clock on pin 9 in Hz, only used with
+    /// [`Pin9Function::CLKIN`]
+    pub clkin_frequency: u32,
+}
+
+impl Default for Pin9 {
+    fn default() -> Self {
+        Self {
+            function: Pin9Function::default(),
+            clkin_frequency: 32_768,
+        }
+    }
 }
 
 #[derive(Clone, Copy, Default, Debug, PartialEq, Eq)]
@@ -141,3 +412,80 @@ pub enum Pin9Function {
     FSYNC = 0b01,
     CLKIN = 0b10,
 }
+
+/// FSYNC settings
+///
+/// When pin 9 is used as FSYNC, the FIFO packet of the first ODR after an
+/// FSYNC pulse carries the time from the pulse to that ODR instead of the ODR
+/// timestamp, see [`FifoPacket::fsync_delay`](crate::fifo::FifoPacket::fsync_delay).
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Fsync {
+    pub polarity: FsyncPolarity,
+    pub tag: FsyncTag,
+}
+
+/// `FSYNC_POLARITY` in `FSYNC_CONFIG`
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum FsyncPolarity {
+    /// Measure from the rising edge of the FSYNC pulse
+    #[default]
+    RisingEdge = 0,
+    /// Measure from the falling edge of the FSYNC pulse
+    FallingEdge = 1,
+}
+
+/// Sensor register whose LSB is replaced by the FSYNC flag, `FSYNC_UI_SEL` in
+/// `FSYNC_CONFIG`
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum FsyncTag {
+    Disabled = 0b000,
+    #[default]
+    Temperature = 0b001,
+    GyroX = 0b010,
+    GyroY = 0b011,
+    GyroZ = 0b100,
+    AccelX = 0b101,
+    AccelY = 0b110,
+    AccelZ = 0b111,
+}
+
+/// Settings shared by the APEX motion functions, see [`crate::apex`]
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Apex {
+    /// Rate the DMP processes the accel data at. The accel ODR has to be at
+    /// least this fast.
+    pub dmp_odr: DmpOdr,
+}
+
+/// `DMP_ODR` in `APEX_CONFIG0`
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum DmpOdr {
+    /// 25 Hz
+    _25Hz = 0b00,
+    /// 50 Hz
+    #[default]
+    _50Hz = 0b10,
+}
+
+impl DmpOdr {
+    pub fn hz(self) -> f32 {
+        match self {
+            DmpOdr::_25Hz => 25.0,
+            DmpOdr::_50Hz => 50.0,
+        }
+    }
+}
+
+/// Byte order of multi-byte values
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum Endian {
+    Little = 0,
+    #[default]
+    Big = 1,
+}
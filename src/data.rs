@@ -0,0 +1,181 @@
+//! Direct reads of the sensor data registers, without the FIFO
+
+use core::ops::Range;
+
+use crate::{
+    config::Endian,
+    register_bank::{bank0, Register},
+    sample::{Sample, DATA_REGISTERS_LEN},
+    Error, Ready, ICM42688,
+};
+
+#[cfg(feature = "async")]
+use crate::interface::AsyncRegisterInterface;
+
+#[cfg(feature = "blocking")]
+use crate::interface::RegisterInterface;
+
+/// First of the sensor data registers
+const TEMP_DATA1_ADDR: u8 = bank0::TEMP_DATA1::ID;
+
+/// Position of the temperature in the sensor data registers
+const TEMPERATURE: Range<usize> = 0..2;
+
+/// Position of the accel data in the sensor data registers
+const ACCEL: Range<usize> = 2..8;
+
+/// Position of the gyro data in the sensor data registers
+const GYRO: Range<usize> = 8..14;
+
+impl<BUS, PIN> ICM42688<BUS, Ready<PIN>> {
+    /// Read the latest temperature, accel and gyro data in one burst
+    ///
+    /// Invalid accel or gyro data (e.g. because the sensor is off) is
+    /// reported as `None`.
+    #[cfg(feature = "async")]
+    pub async fn async_read_all(&mut self) -> Result<Sample, Error<BUS::Error>>
+    where
+        BUS: AsyncRegisterInterface,
+    {
+        self.async_read_data(0..DATA_REGISTERS_LEN).await
+    }
+
+    /// Read the latest temperature, accel and gyro data in one burst
+    ///
+    /// Invalid accel or gyro data (e.g. because the sensor is off) is
+    /// reported as `None`.
+    #[cfg(feature = "blocking")]
+    pub fn read_all(&mut self) -> Result<Sample, Error<BUS::Error>>
+    where
+        BUS: RegisterInterface,
+    {
+        self.read_data(0..DATA_REGISTERS_LEN)
+    }
+
+    /// Read the latest acceleration in m/s², `None` if the data is invalid
+    #[cfg(feature = "async")]
+    pub async fn async_read_accel(&mut self) -> Result<Option<[f32; 3]>, Error<BUS::Error>>
+    where
+        BUS: AsyncRegisterInterface,
+    {
+        Ok(self.async_read_data(ACCEL).await?.accel)
+    }
+
+    /// Read the latest acceleration in m/s², `None` if the data is invalid
+    #[cfg(feature = "blocking")]
+    pub fn read_accel(&mut self) -> Result<Option<[f32; 3]>, Error<BUS::Error>>
+    where
+        BUS: RegisterInterface,
+    {
+        Ok(self.read_data(ACCEL)?.accel)
+    }
+
+    /// Read the latest angular rate in rad/s, `None` if the data is invalid
+    #[cfg(feature = "async")]
+    pub async fn async_read_gyro(&mut self) -> Result<Option<[f32; 3]>, Error<BUS::Error>>
+    where
+        BUS: AsyncRegisterInterface,
+    {
+        Ok(self.async_read_data(GYRO).await?.gyro)
+    }
+
+    /// Read the latest angular rate in rad/s, `None` if the data is invalid
+    #[cfg(feature = "blocking")]
+    pub fn read_gyro(&mut self) -> Result<Option<[f32; 3]>, Error<BUS::Error>>
+    where
+        BUS: RegisterInterface,
+    {
+        Ok(self.read_data(GYRO)?.gyro)
+    }
+
+    /// Read the latest temperature in °C
+    #[cfg(feature = "async")]
+    pub async fn async_read_temperature(&mut self) -> Result<f32, Error<BUS::Error>>
+    where
+        BUS: AsyncRegisterInterface,
+    {
+        Ok(self.async_read_data(TEMPERATURE).await?.temperature)
+    }
+
+    /// Read the latest temperature in °C
+    #[cfg(feature = "blocking")]
+    pub fn read_temperature(&mut self) -> Result<f32, Error<BUS::Error>>
+    where
+        BUS: RegisterInterface,
+    {
+        Ok(self.read_data(TEMPERATURE)?.temperature)
+    }
+
+    /// Set the byte order of the sensor data registers
+    ///
+    /// `initialize` selects big endian. The FIFO parser expects big endian
+    /// data.
+    #[cfg(feature = "async")]
+    pub async fn async_set_sensor_data_endian(
+        &mut self,
+        endian: Endian,
+    ) -> Result<(), Error<BUS::Error>>
+    where
+        BUS: AsyncRegisterInterface,
+    {
+        let mut bank0 = self.ll.try_bank::<0>()?;
+        bank0
+            .intf_config0()
+            .async_modify(|w| w.sensor_data_endian(endian as u8))
+            .await?;
+        self.state.inner.sensor_data_endian = endian;
+        Ok(())
+    }
+
+    /// Set the byte order of the sensor data registers
+    ///
+    /// `initialize` selects big endian. The FIFO parser expects big endian
+    /// data.
+    #[cfg(feature = "blocking")]
+    pub fn set_sensor_data_endian(&mut self, endian: Endian) -> Result<(), Error<BUS::Error>>
+    where
+        BUS: RegisterInterface,
+    {
+        let mut bank0 = self.ll.try_bank::<0>()?;
+        bank0
+            .intf_config0()
+            .modify(|_, w| w.sensor_data_endian(endian as u8))?;
+        self.state.inner.sensor_data_endian = endian;
+        Ok(())
+    }
+
+    /// Read `range` of the sensor data registers, the rest is left zeroed
+    #[cfg(feature = "async")]
+    async fn async_read_data(&mut self, range: Range<usize>) -> Result<Sample, Error<BUS::Error>>
+    where
+        BUS: AsyncRegisterInterface,
+    {
+        self.ll.try_bank::<0>()?;
+        let mut data = [0; DATA_REGISTERS_LEN];
+        self.ll
+            .bus
+            .read_regs(TEMP_DATA1_ADDR + range.start as u8, &mut data[range])
+            .await
+            .map_err(Error::Bus)?;
+        Ok(self.data_to_sample(&data))
+    }
+
+    /// Read `range` of the sensor data registers, the rest is left zeroed
+    #[cfg(feature = "blocking")]
+    fn read_data(&mut self, range: Range<usize>) -> Result<Sample, Error<BUS::Error>>
+    where
+        BUS: RegisterInterface,
+    {
+        self.ll.try_bank::<0>()?;
+        let mut data = [0; DATA_REGISTERS_LEN];
+        self.ll
+            .bus
+            .read_regs(TEMP_DATA1_ADDR + range.start as u8, &mut data[range])
+            .map_err(Error::Bus)?;
+        Ok(self.data_to_sample(&data))
+    }
+
+    fn data_to_sample(&self, data: &[u8; DATA_REGISTERS_LEN]) -> Sample {
+        Sample::from_registers(data, self.state.inner.sensor_data_endian, self.full_scale())
+    }
+}
@@ -0,0 +1,66 @@
+use core::fmt;
+
+use crate::{interface::Interface, ll::BankSelectionError, register_bank};
+
+/// Errors returned by the top-level driver
+///
+/// `E` is the error type of the bus.
+#[derive(Debug, defmt::Format, Clone, Copy, PartialEq, Eq)]
+pub enum Error<E> {
+    /// The bus reported an error
+    Bus(E),
+    /// WHO_AM_I returned an unexpected value
+    WrongChipId(u8),
+    /// The driver's view of the selected register bank is out of sync
+    BankMismatch,
+    /// The provided buffer can't hold the requested data
+    BufferTooSmall,
+    /// The device did not finish an operation in time
+    ///
+    /// Reserved for operations that poll the device, no method returns it
+    /// at the moment.
+    Timeout,
+    /// The method is not available in the configured data mode
+    WrongDataMode,
+    /// The interrupt pin reported an error
+    Pin,
+    /// The configured ODR is not supported by the requested function
+    UnsupportedOdr,
+}
+
+impl<E> fmt::Display for Error<E>
+where
+    E: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Bus(e) => write!(f, "Bus error: {:?}", e),
+            Error::WrongChipId(id) => write!(f, "Wrong chip ID: 0x{:02x}", id),
+            Error::BankMismatch => write!(f, "Register bank mismatch"),
+            Error::BufferTooSmall => write!(f, "Buffer too small"),
+            Error::Timeout => write!(f, "Timeout"),
+            Error::WrongDataMode => write!(f, "Not available in the configured data mode"),
+            Error::Pin => write!(f, "Interrupt pin error"),
+            Error::UnsupportedOdr => write!(f, "ODR not supported by this function"),
+        }
+    }
+}
+
+impl<E> core::error::Error for Error<E> where E: fmt::Debug {}
+
+impl<BUS> From<register_bank::Error<BUS>> for Error<BUS::Error>
+where
+    BUS: Interface,
+{
+    fn from(error: register_bank::Error<BUS>) -> Self {
+        match error {
+            register_bank::Error::Transfer(e) => Error::Bus(e),
+        }
+    }
+}
+
+impl<E> From<BankSelectionError> for Error<E> {
+    fn from(_: BankSelectionError) -> Self {
+        Error::BankMismatch
+    }
+}
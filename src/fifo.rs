@@ -5,13 +5,13 @@ use defmt::Format;
 #[bitsize(8)]
 #[derive(DebugBits, FromBits, PartialEq)]
 pub struct FifoHeader {
-    odr_changed_gyro: u1, // 1: The ODR for gyro is different for this gyro data packet compared to the previous gyro packet
-    odr_changed_accel: u1, // 1: The ODR for accel is different for this accel data packet compared to the previous accel packet
-    has_timestamp_fsync: u2, // 10: Packet contains ODR Timestamp
-    has_20bit: u1, // 1: Packet has a new and valid sample of extended 20-bit data for gyro and/or accel
-    has_gyro: u1, // 1: Packet is sized so that gyro data have location in the packet, FIFO_GYRO_EN must be 1
-    has_accel: u1, // 1: Packet is sized so that accel data have location in the packet, FIFO_ACCEL_EN must be 1
-    header_msg: u1, // 1: FIFO is empty
+    pub odr_changed_gyro: u1, // 1: The ODR for gyro is different for this gyro data packet compared to the previous gyro packet
+    pub odr_changed_accel: u1, // 1: The ODR for accel is different for this accel data packet compared to the previous accel packet
+    pub has_timestamp_fsync: u2, // 10: Packet contains ODR Timestamp
+    pub has_20bit: u1, // 1: Packet has a new and valid sample of extended 20-bit data for gyro and/or accel
+    pub has_gyro: u1, // 1: Packet is sized so that gyro data have location in the packet, FIFO_GYRO_EN must be 1
+    pub has_accel: u1, // 1: Packet is sized so that accel data have location in the packet, FIFO_ACCEL_EN must be 1
+    pub header_msg: u1, // 1: FIFO is empty
 }
 
 impl defmt::Format for FifoHeader {
@@ -28,6 +28,144 @@ impl defmt::Format for FifoHeader {
     }
 }
 
+#[inline]
+fn convert_parts_to_16bit(high_8: u8, low_8: u8) -> i16 {
+    i16::from_be_bytes([high_8, low_8])
+}
+
+/// Accel and temperature, used when only the accel is written to the FIFO
+#[derive(Debug, Clone, Copy, Format, PartialEq, NoUninit, AnyBitPattern, Default)]
+#[repr(C)]
+pub struct FifoPacket1 {
+    pub fifo_header: u8,
+    pub accel_data_x1: u8, // Accel X [15:8]
+    pub accel_data_x0: u8, // Accel X [7:0]
+    pub accel_data_y1: u8, // Accel Y [15:8]
+    pub accel_data_y0: u8, // Accel Y [7:0]
+    pub accel_data_z1: u8, // Accel Z [15:8]
+    pub accel_data_z0: u8, // Accel Z [7:0]
+    pub temp_data0: u8,    // Temperature[7:0]
+}
+
+impl FifoPacket1 {
+    pub fn fifo_header(&self) -> FifoHeader {
+        FifoHeader::from(self.fifo_header)
+    }
+
+    pub fn accel_data_x(&self) -> i16 {
+        convert_parts_to_16bit(self.accel_data_x1, self.accel_data_x0)
+    }
+
+    pub fn accel_data_y(&self) -> i16 {
+        convert_parts_to_16bit(self.accel_data_y1, self.accel_data_y0)
+    }
+
+    pub fn accel_data_z(&self) -> i16 {
+        convert_parts_to_16bit(self.accel_data_z1, self.accel_data_z0)
+    }
+
+    pub fn temperature_raw(&self) -> u8 {
+        self.temp_data0
+    }
+}
+
+/// Gyro and temperature, used when only the gyro is written to the FIFO
+#[derive(Debug, Clone, Copy, Format, PartialEq, NoUninit, AnyBitPattern, Default)]
+#[repr(C)]
+pub struct FifoPacket2 {
+    pub fifo_header: u8,
+    pub gyro_data_x1: u8, // Gyro X [15:8]
+    pub gyro_data_x0: u8, // Gyro X [7:0]
+    pub gyro_data_y1: u8, // Gyro Y [15:8]
+    pub gyro_data_y0: u8, // Gyro Y [7:0]
+    pub gyro_data_z1: u8, // Gyro Z [15:8]
+    pub gyro_data_z0: u8, // Gyro Z [7:0]
+    pub temp_data0: u8,   // Temperature[7:0]
+}
+
+impl FifoPacket2 {
+    pub fn fifo_header(&self) -> FifoHeader {
+        FifoHeader::from(self.fifo_header)
+    }
+
+    pub fn gyro_data_x(&self) -> i16 {
+        convert_parts_to_16bit(self.gyro_data_x1, self.gyro_data_x0)
+    }
+
+    pub fn gyro_data_y(&self) -> i16 {
+        convert_parts_to_16bit(self.gyro_data_y1, self.gyro_data_y0)
+    }
+
+    pub fn gyro_data_z(&self) -> i16 {
+        convert_parts_to_16bit(self.gyro_data_z1, self.gyro_data_z0)
+    }
+
+    pub fn temperature_raw(&self) -> u8 {
+        self.temp_data0
+    }
+}
+
+/// Accel, gyro, temperature and timestamp in 16-bit resolution
+#[derive(Debug, Clone, Copy, Format, PartialEq, NoUninit, AnyBitPattern, Default)]
+#[repr(C)]
+pub struct FifoPacket3 {
+    pub fifo_header: u8,
+    pub accel_data_x1: u8, // Accel X [15:8]
+    pub accel_data_x0: u8, // Accel X [7:0]
+    pub accel_data_y1: u8, // Accel Y [15:8]
+    pub accel_data_y0: u8, // Accel Y [7:0]
+    pub accel_data_z1: u8, // Accel Z [15:8]
+    pub accel_data_z0: u8, // Accel Z [7:0]
+    pub gyro_data_x1: u8,  // Gyro X [15:8]
+    pub gyro_data_x0: u8,  // Gyro X [7:0]
+    pub gyro_data_y1: u8,  // Gyro Y [15:8]
+    pub gyro_data_y0: u8,  // Gyro Y [7:0]
+    pub gyro_data_z1: u8,  // Gyro Z [15:8]
+    pub gyro_data_z0: u8,  // Gyro Z [7:0]
+    pub temp_data0: u8,    // Temperature[7:0]
+    pub timestamp_h: u8,   // TimeStamp[15:8]
+    pub timestamp_l: u8,   // TimeStamp[7:0]
+}
+
+impl FifoPacket3 {
+    pub fn fifo_header(&self) -> FifoHeader {
+        FifoHeader::from(self.fifo_header)
+    }
+
+    pub fn accel_data_x(&self) -> i16 {
+        convert_parts_to_16bit(self.accel_data_x1, self.accel_data_x0)
+    }
+
+    pub fn accel_data_y(&self) -> i16 {
+        convert_parts_to_16bit(self.accel_data_y1, self.accel_data_y0)
+    }
+
+    pub fn accel_data_z(&self) -> i16 {
+        convert_parts_to_16bit(self.accel_data_z1, self.accel_data_z0)
+    }
+
+    pub fn gyro_data_x(&self) -> i16 {
+        convert_parts_to_16bit(self.gyro_data_x1, self.gyro_data_x0)
+    }
+
+    pub fn gyro_data_y(&self) -> i16 {
+        convert_parts_to_16bit(self.gyro_data_y1, self.gyro_data_y0)
+    }
+
+    pub fn gyro_data_z(&self) -> i16 {
+        convert_parts_to_16bit(self.gyro_data_z1, self.gyro_data_z0)
+    }
+
+    pub fn temperature_raw(&self) -> u8 {
+        self.temp_data0
+    }
+
+    pub fn timestamp(&self) -> u16 {
+        ((self.timestamp_h as u16) << 8) | self.timestamp_l as u16
+    }
+}
+
+/// Accel, gyro, temperature and timestamp in 20-bit resolution
 #[derive(Debug, Clone, Copy, Format, PartialEq, NoUninit, AnyBitPattern, Default)]
 #[repr(C)]
 pub struct FifoPacket4 {
@@ -115,5 +253,233 @@ impl FifoPacket4 {
     }
 }
 
-// Assert that the size of the struct is 20 bytes
+/// `has_timestamp_fsync` value of packets carrying an ODR timestamp
+pub(crate) const HEADER_ODR_TIMESTAMP: u8 = 0b10;
+
+/// `has_timestamp_fsync` value of packets carrying an FSYNC timestamp
+pub(crate) const HEADER_FSYNC_TIMESTAMP: u8 = 0b11;
+
+/// A packet of any of the FIFO packet structures
+#[derive(Debug, Clone, Copy, Format, PartialEq)]
+pub enum FifoPacket {
+    Packet1(FifoPacket1),
+    Packet2(FifoPacket2),
+    Packet3(FifoPacket3),
+    Packet4(FifoPacket4),
+}
+
+impl FifoPacket {
+    pub fn fifo_header(&self) -> FifoHeader {
+        match self {
+            FifoPacket::Packet1(p) => p.fifo_header(),
+            FifoPacket::Packet2(p) => p.fifo_header(),
+            FifoPacket::Packet3(p) => p.fifo_header(),
+            FifoPacket::Packet4(p) => p.fifo_header(),
+        }
+    }
+
+    /// The raw 16-bit timestamp field of packets 3 and 4
+    ///
+    /// Holds the ODR timestamp or the FSYNC delay depending on the header.
+    pub fn timestamp(&self) -> Option<u16> {
+        match self {
+            FifoPacket::Packet1(_) | FifoPacket::Packet2(_) => None,
+            FifoPacket::Packet3(p) => Some(p.timestamp()),
+            FifoPacket::Packet4(p) => Some(p.timestamp()),
+        }
+    }
+
+    /// Time from the FSYNC pulse to the ODR of this packet
+    ///
+    /// Only the first packet after an FSYNC pulse is tagged, the delay is in
+    /// timestamp ticks. Returns `None` for untagged packets.
+    pub fn fsync_delay(&self) -> Option<u16> {
+        if self.fifo_header().has_timestamp_fsync().value() == HEADER_FSYNC_TIMESTAMP {
+            self.timestamp()
+        } else {
+            None
+        }
+    }
+
+    /// Size of the packet in bytes, including the header
+    pub fn size(&self) -> usize {
+        match self {
+            FifoPacket::Packet1(_) => core::mem::size_of::<FifoPacket1>(),
+            FifoPacket::Packet2(_) => core::mem::size_of::<FifoPacket2>(),
+            FifoPacket::Packet3(_) => core::mem::size_of::<FifoPacket3>(),
+            FifoPacket::Packet4(_) => core::mem::size_of::<FifoPacket4>(),
+        }
+    }
+}
+
+impl FifoHeader {
+    /// Size in bytes of the packet starting with this header
+    ///
+    /// Returns `None` for the "FIFO empty" marker and for headers that don't
+    /// describe any packet.
+    pub fn packet_size(&self) -> Option<usize> {
+        if self.header_msg().value() == 1 {
+            return None;
+        }
+        match (
+            self.has_20bit().value(),
+            self.has_accel().value(),
+            self.has_gyro().value(),
+        ) {
+            (1, _, _) => Some(core::mem::size_of::<FifoPacket4>()),
+            (0, 1, 1) => Some(core::mem::size_of::<FifoPacket3>()),
+            (0, 1, 0) => Some(core::mem::size_of::<FifoPacket1>()),
+            (0, 0, 1) => Some(core::mem::size_of::<FifoPacket2>()),
+            _ => None,
+        }
+    }
+}
+
+/// Parser for the raw FIFO data
+///
+/// Walks a buffer of FIFO bytes (without the INT_STATUS and FIFO count
+/// prefix) and yields one [`FifoPacket`] per packet. The size and type of each
+/// packet follow from its header, so packets of different sizes can be mixed,
+/// e.g. when the gyro and accel ODRs differ.
+///
+/// Iteration stops at the "FIFO empty" marker, at a header that does not
+/// describe a packet and when the rest of the buffer is too short to hold the
+/// next packet. The bytes that were not consumed are available through
+/// [`FifoParser::remaining`].
+#[derive(Debug, Clone)]
+pub struct FifoParser<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> FifoParser<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        FifoParser { data }
+    }
+
+    /// Bytes that have not been parsed yet
+    pub fn remaining(&self) -> &'a [u8] {
+        self.data
+    }
+}
+
+impl Iterator for FifoParser<'_> {
+    type Item = FifoPacket;
+
+    fn next(&mut self) -> Option<FifoPacket> {
+        let header = FifoHeader::from(*self.data.first()?);
+        let size = header.packet_size()?;
+        if self.data.len() < size {
+            return None;
+        }
+        let (bytes, rest) = self.data.split_at(size);
+        self.data = rest;
+
+        let packet = if header.has_20bit().value() == 1 {
+            FifoPacket::Packet4(bytemuck::pod_read_unaligned(bytes))
+        } else {
+            match (header.has_accel().value(), header.has_gyro().value()) {
+                (1, 1) => FifoPacket::Packet3(bytemuck::pod_read_unaligned(bytes)),
+                (1, _) => FifoPacket::Packet1(bytemuck::pod_read_unaligned(bytes)),
+                _ => FifoPacket::Packet2(bytemuck::pod_read_unaligned(bytes)),
+            }
+        };
+        Some(packet)
+    }
+}
+
+/// Outcome of [`ICM42688::read_fifo_into`](crate::ICM42688::read_fifo_into)
+#[derive(Debug, Clone, Copy, Format, PartialEq, Eq)]
+pub struct FifoReadResult {
+    /// `INT_STATUS` as read together with the FIFO count
+    ///
+    /// `INT_STATUS2` and `INT_STATUS3` are not read, so their flags (wake on
+    /// motion, significant motion and the DMP events) are always false here.
+    /// Use [`read_interrupt_status`](crate::ICM42688::read_interrupt_status)
+    /// for those.
+    pub int_status: crate::interrupt::InterruptStatus,
+    /// Number of bytes that were in the FIFO
+    pub fifo_count: usize,
+    /// Number of bytes written to the start of the buffer
+    pub bytes_read: usize,
+    /// Number of complete packets in the bytes that were read
+    pub packets: usize,
+    /// The FIFO held more data than fit into the buffer
+    pub data_left: bool,
+}
+
+// Assert that the size of the structs matches the packet sizes
+const _SIZE_CHECK_1: usize = (core::mem::size_of::<FifoPacket1>() == 8) as usize - 1;
+const _SIZE_CHECK_2: usize = (core::mem::size_of::<FifoPacket2>() == 8) as usize - 1;
+const _SIZE_CHECK_3: usize = (core::mem::size_of::<FifoPacket3>() == 16) as usize - 1;
 const _SIZE_CHECK: usize = (core::mem::size_of::<FifoPacket4>() == 20) as usize - 1;
+
+#[cfg(test)]
+mod test {
+    extern crate alloc;
+    use super::*;
+    use alloc::vec::Vec;
+
+    const HEADER_ACCEL: u8 = 0b0100_0000;
+    const HEADER_GYRO: u8 = 0b0010_0000;
+    const HEADER_20: u8 = 0b0001_0000;
+    const HEADER_EMPTY: u8 = 0b1000_0000;
+
+    fn packet(header: u8, size: usize, fill: u8) -> Vec<u8> {
+        let mut bytes = alloc::vec![fill; size];
+        bytes[0] = header;
+        bytes
+    }
+
+    #[test]
+    fn test_parse_mixed() {
+        let mut data = Vec::new();
+        data.extend(packet(HEADER_ACCEL | HEADER_GYRO, 16, 1));
+        data.extend(packet(HEADER_ACCEL, 8, 2));
+        data.extend(packet(HEADER_GYRO, 8, 3));
+        data.extend(packet(HEADER_ACCEL | HEADER_GYRO | HEADER_20, 20, 4));
+
+        let packets: Vec<_> = FifoParser::new(&data).collect();
+        assert_eq!(packets.len(), 4);
+        assert!(matches!(packets[0], FifoPacket::Packet3(p) if p.accel_data_x() == 0x0101));
+        assert!(matches!(packets[1], FifoPacket::Packet1(p) if p.temperature_raw() == 2));
+        assert!(matches!(packets[2], FifoPacket::Packet2(p) if p.gyro_data_z() == 0x0303));
+        assert!(matches!(packets[3], FifoPacket::Packet4(p) if p.timestamp() == 0x0404));
+        assert_eq!(packets[3].size(), 20);
+    }
+
+    #[test]
+    fn test_parse_stops_on_empty() {
+        let mut data = Vec::new();
+        data.extend(packet(HEADER_ACCEL, 8, 0));
+        data.extend(packet(HEADER_EMPTY, 8, 0xFF));
+        data.extend(packet(HEADER_ACCEL, 8, 0));
+
+        let mut parser = FifoParser::new(&data);
+        assert!(matches!(parser.next(), Some(FifoPacket::Packet1(_))));
+        assert_eq!(parser.next(), None);
+        assert_eq!(parser.remaining().len(), 16);
+    }
+
+    #[test]
+    fn test_parse_truncated() {
+        let mut data = packet(HEADER_ACCEL | HEADER_GYRO | HEADER_20, 20, 0);
+        data.extend(&packet(HEADER_ACCEL | HEADER_GYRO | HEADER_20, 20, 0)[..11]);
+
+        let mut parser = FifoParser::new(&data);
+        assert!(matches!(parser.next(), Some(FifoPacket::Packet4(_))));
+        assert_eq!(parser.next(), None);
+        assert_eq!(parser.remaining().len(), 11);
+    }
+
+    #[test]
+    fn test_fsync_delay() {
+        let mut data = packet(HEADER_ACCEL | HEADER_GYRO | 0b1000, 16, 0);
+        data.extend(packet(HEADER_ACCEL | HEADER_GYRO | 0b1100, 16, 0));
+        data[31] = 42;
+
+        let packets: Vec<_> = FifoParser::new(&data).collect();
+        assert_eq!(packets[0].timestamp(), Some(0));
+        assert_eq!(packets[0].fsync_delay(), None);
+        assert_eq!(packets[1].fsync_delay(), Some(42));
+    }
+}
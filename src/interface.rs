@@ -0,0 +1,248 @@
+//! Bus interfaces
+//!
+//! The ICM426xx can be wired over SPI or I2C. Register accesses go through the
+//! [`RegisterInterface`] (blocking) and [`AsyncRegisterInterface`] traits so the
+//! same driver can be used with either bus.
+//!
+//! Any `SpiDevice` can be used directly, I2C buses have to be wrapped in an
+//! [`I2cInterface`] together with the device address. Other transports and test
+//! doubles can be plugged in by implementing the traits.
+
+use embedded_hal::{i2c, spi};
+
+/// I2C address of the device when the AP_AD0 pin is pulled low
+pub const I2C_ADDRESS_AD0_LOW: u8 = 0x68;
+
+/// I2C address of the device when the AP_AD0 pin is pulled high
+pub const I2C_ADDRESS_AD0_HIGH: u8 = 0x69;
+
+/// Short accesses are copied into a stack buffer so they go out as one transfer
+const SCRATCH_LEN: usize = 16;
+
+/// The serial interface used to talk to the device
+#[derive(Debug, defmt::Format, Clone, Copy, PartialEq, Eq)]
+pub enum InterfaceKind {
+    Spi,
+    I2c,
+}
+
+/// Properties shared by the blocking and async register interfaces
+pub trait Interface {
+    /// Error type of the underlying bus
+    type Error: core::fmt::Debug;
+
+    /// The serial interface that is used
+    ///
+    /// `initialize` uses this to disable the serial interface that is not in use.
+    const KIND: InterfaceKind;
+}
+
+/// Blocking register level access to the device
+///
+/// This is the only thing the driver needs from the bus. Reads and writes start
+/// at `addr` and use the auto-increment of the device for multi-byte accesses.
+/// The register bank is selected by the driver through `REG_BANK_SEL`, the
+/// implementation does not need to keep track of it.
+#[cfg(feature = "blocking")]
+pub trait RegisterInterface: Interface {
+    /// Read `buf.len()` bytes starting at register `addr`
+    fn read_regs(&mut self, addr: u8, buf: &mut [u8]) -> Result<(), Self::Error>;
+
+    /// Write `data` starting at register `addr`
+    fn write_regs(&mut self, addr: u8, data: &[u8]) -> Result<(), Self::Error>;
+}
+
+/// Async register level access to the device
+///
+/// Same as [`RegisterInterface`], but for async buses.
+#[cfg(feature = "async")]
+#[allow(async_fn_in_trait)]
+pub trait AsyncRegisterInterface: Interface {
+    /// Read `buf.len()` bytes starting at register `addr`
+    async fn read_regs(&mut self, addr: u8, buf: &mut [u8]) -> Result<(), Self::Error>;
+
+    /// Write `data` starting at register `addr`
+    async fn write_regs(&mut self, addr: u8, data: &[u8]) -> Result<(), Self::Error>;
+}
+
+impl<SPI> Interface for SPI
+where
+    SPI: spi::ErrorType,
+{
+    type Error = SPI::Error;
+
+    const KIND: InterfaceKind = InterfaceKind::Spi;
+}
+
+#[cfg(feature = "blocking")]
+impl<SPI> RegisterInterface for SPI
+where
+    SPI: spi::SpiDevice,
+{
+    fn read_regs(&mut self, addr: u8, buf: &mut [u8]) -> Result<(), Self::Error> {
+        let header = addr | 0x80; // Read bit set
+
+        if buf.len() <= SCRATCH_LEN {
+            let mut scratch = [0; SCRATCH_LEN + 1];
+            let scratch = &mut scratch[..buf.len() + 1];
+            scratch[0] = header;
+            self.transfer_in_place(scratch)?;
+            buf.copy_from_slice(&scratch[1..]);
+            Ok(())
+        } else {
+            self.transaction(&mut [spi::Operation::Write(&[header]), spi::Operation::Read(buf)])
+        }
+    }
+
+    fn write_regs(&mut self, addr: u8, data: &[u8]) -> Result<(), Self::Error> {
+        let header = addr & 0x7f;
+
+        if data.len() <= SCRATCH_LEN {
+            let mut scratch = [0; SCRATCH_LEN + 1];
+            let scratch = &mut scratch[..data.len() + 1];
+            scratch[0] = header;
+            scratch[1..].copy_from_slice(data);
+            self.write(scratch)
+        } else {
+            self.transaction(&mut [
+                spi::Operation::Write(&[header]),
+                spi::Operation::Write(data),
+            ])
+        }
+    }
+}
+
+#[cfg(feature = "async")]
+impl<SPI> AsyncRegisterInterface for SPI
+where
+    SPI: embedded_hal_async::spi::SpiDevice,
+{
+    async fn read_regs(&mut self, addr: u8, buf: &mut [u8]) -> Result<(), Self::Error> {
+        let header = addr | 0x80; // Read bit set
+
+        if buf.len() <= SCRATCH_LEN {
+            let mut scratch = [0; SCRATCH_LEN + 1];
+            let scratch = &mut scratch[..buf.len() + 1];
+            scratch[0] = header;
+            self.transfer_in_place(scratch).await?;
+            buf.copy_from_slice(&scratch[1..]);
+            Ok(())
+        } else {
+            self.transaction(&mut [spi::Operation::Write(&[header]), spi::Operation::Read(buf)])
+                .await
+        }
+    }
+
+    async fn write_regs(&mut self, addr: u8, data: &[u8]) -> Result<(), Self::Error> {
+        let header = addr & 0x7f;
+
+        if data.len() <= SCRATCH_LEN {
+            let mut scratch = [0; SCRATCH_LEN + 1];
+            let scratch = &mut scratch[..data.len() + 1];
+            scratch[0] = header;
+            scratch[1..].copy_from_slice(data);
+            self.write(scratch).await
+        } else {
+            self.transaction(&mut [
+                spi::Operation::Write(&[header]),
+                spi::Operation::Write(data),
+            ])
+            .await
+        }
+    }
+}
+
+/// An I2C bus together with the address of the device
+#[derive(Debug)]
+pub struct I2cInterface<I2C> {
+    i2c: I2C,
+    address: u8,
+}
+
+impl<I2C> I2cInterface<I2C> {
+    /// Create a new I2C interface
+    ///
+    /// `address` is usually [`I2C_ADDRESS_AD0_LOW`] or [`I2C_ADDRESS_AD0_HIGH`]
+    /// depending on the level of the AP_AD0 pin.
+    pub fn new(i2c: I2C, address: u8) -> Self {
+        I2cInterface { i2c, address }
+    }
+
+    /// The I2C address of the device
+    pub fn address(&self) -> u8 {
+        self.address
+    }
+
+    /// Direct access to the I2C bus
+    pub fn bus(&mut self) -> &mut I2C {
+        &mut self.i2c
+    }
+
+    /// Release the I2C bus
+    pub fn release(self) -> I2C {
+        self.i2c
+    }
+}
+
+impl<I2C> Interface for I2cInterface<I2C>
+where
+    I2C: i2c::ErrorType,
+{
+    type Error = I2C::Error;
+
+    const KIND: InterfaceKind = InterfaceKind::I2c;
+}
+
+#[cfg(feature = "blocking")]
+impl<I2C> RegisterInterface for I2cInterface<I2C>
+where
+    I2C: i2c::I2c,
+{
+    fn read_regs(&mut self, addr: u8, buf: &mut [u8]) -> Result<(), Self::Error> {
+        self.i2c.write_read(self.address, &[addr], buf)
+    }
+
+    fn write_regs(&mut self, addr: u8, data: &[u8]) -> Result<(), Self::Error> {
+        if data.len() <= SCRATCH_LEN {
+            let mut scratch = [0; SCRATCH_LEN + 1];
+            let scratch = &mut scratch[..data.len() + 1];
+            scratch[0] = addr;
+            scratch[1..].copy_from_slice(data);
+            self.i2c.write(self.address, scratch)
+        } else {
+            // Adjacent writes are merged into a single I2C write
+            self.i2c.transaction(
+                self.address,
+                &mut [i2c::Operation::Write(&[addr]), i2c::Operation::Write(data)],
+            )
+        }
+    }
+}
+
+#[cfg(feature = "async")]
+impl<I2C> AsyncRegisterInterface for I2cInterface<I2C>
+where
+    I2C: embedded_hal_async::i2c::I2c,
+{
+    async fn read_regs(&mut self, addr: u8, buf: &mut [u8]) -> Result<(), Self::Error> {
+        self.i2c.write_read(self.address, &[addr], buf).await
+    }
+
+    async fn write_regs(&mut self, addr: u8, data: &[u8]) -> Result<(), Self::Error> {
+        if data.len() <= SCRATCH_LEN {
+            let mut scratch = [0; SCRATCH_LEN + 1];
+            let scratch = &mut scratch[..data.len() + 1];
+            scratch[0] = addr;
+            scratch[1..].copy_from_slice(data);
+            self.i2c.write(self.address, scratch).await
+        } else {
+            // Adjacent writes are merged into a single I2C write
+            self.i2c
+                .transaction(
+                    self.address,
+                    &mut [i2c::Operation::Write(&[addr]), i2c::Operation::Write(data)],
+                )
+                .await
+        }
+    }
+}
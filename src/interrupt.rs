@@ -0,0 +1,572 @@
+//! Interrupt status and routing
+//!
+//! [`Interrupts`] is a set of interrupt sources. It is used both for the
+//! flags read from `INT_STATUS`, `INT_STATUS2` and `INT_STATUS3` and to select
+//! the sources routed to INT1 or INT2. The electrical configuration of the
+//! pins is part of the [`Config`](crate::Config).
+
+use core::ops::{BitAnd, BitOr, BitOrAssign};
+
+use crate::{
+    register_bank::{
+        bank0::{INT_SOURCE0, INT_SOURCE1, INT_SOURCE3, INT_SOURCE4, INT_STATUS, INT_STATUS2},
+        bank4::{INT_SOURCE6, INT_SOURCE7},
+        Mutation, Register, BANK0, BANK4,
+    },
+    Error, NoPin, Ready, ICM42688,
+};
+
+#[cfg(feature = "async")]
+use crate::interface::AsyncRegisterInterface;
+
+#[cfg(feature = "async")]
+use crate::config::{IntMode, IntPin, Polarity};
+
+#[cfg(feature = "async")]
+use embedded_hal_async::digital::Wait;
+
+#[cfg(feature = "blocking")]
+use crate::interface::RegisterInterface;
+
+/// Set of interrupt sources
+///
+/// Byte 0 holds the `INT_STATUS` flags, byte 1 the `INT_STATUS2` flags and
+/// byte 2 the `INT_STATUS3` flags. The same bits enable the sources in the
+/// `INT_SOURCE` registers.
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq, defmt::Format)]
+pub struct Interrupts(u32);
+
+/// Flags read from the interrupt status registers
+///
+/// The flags are cleared by the device when the registers are read.
+pub type InterruptStatus = Interrupts;
+
+impl Interrupts {
+    /// AGC ready
+    pub const AGC_READY: Self = Self(1 << 0);
+    /// The FIFO is full
+    pub const FIFO_FULL: Self = Self(1 << 1);
+    /// The FIFO reached the watermark
+    pub const FIFO_THRESHOLD: Self = Self(1 << 2);
+    /// New data is available in the data registers
+    pub const DATA_READY: Self = Self(1 << 3);
+    /// Software reset is complete
+    pub const RESET_DONE: Self = Self(1 << 4);
+    /// PLL ready
+    pub const PLL_READY: Self = Self(1 << 5);
+    /// UI FSYNC
+    pub const UI_FSYNC: Self = Self(1 << 6);
+    /// Wake on motion on the X axis
+    pub const WOM_X: Self = Self(1 << 8);
+    /// Wake on motion on the Y axis
+    pub const WOM_Y: Self = Self(1 << 9);
+    /// Wake on motion on the Z axis
+    pub const WOM_Z: Self = Self(1 << 10);
+    /// Wake on motion on any axis
+    pub const WOM: Self = Self(0b111 << 8);
+    /// Significant motion
+    pub const SMD: Self = Self(1 << 11);
+    /// Tap detected
+    pub const TAP: Self = Self(1 << 16);
+    /// Raise to wake/sleep: sleep event
+    pub const SLEEP: Self = Self(1 << 17);
+    /// Raise to wake/sleep: wake event
+    pub const WAKE: Self = Self(1 << 18);
+    /// Tilt detected
+    pub const TILT: Self = Self(1 << 19);
+    /// The pedometer step count overflowed
+    pub const STEP_COUNT_OVERFLOW: Self = Self(1 << 20);
+    /// Step detected
+    pub const STEP: Self = Self(1 << 21);
+
+    /// Mask of the defined bits in each status register
+    const VALID: u32 = 0x3f_0f_7f;
+
+    pub const fn empty() -> Self {
+        Interrupts(0)
+    }
+
+    /// Undefined bits are ignored
+    pub const fn from_bits(bits: u32) -> Self {
+        Interrupts(bits & Self::VALID)
+    }
+
+    pub const fn bits(self) -> u32 {
+        self.0
+    }
+
+    /// Combine the values of `INT_STATUS`, `INT_STATUS2` and `INT_STATUS3`
+    pub fn from_registers(registers: [u8; 3]) -> Self {
+        Self::from_bits(u32::from_le_bytes([
+            registers[0],
+            registers[1],
+            registers[2],
+            0,
+        ]))
+    }
+
+    /// Split into values for `INT_STATUS`, `INT_STATUS2` and `INT_STATUS3`
+    pub fn to_registers(self) -> [u8; 3] {
+        let [status, status2, status3, _] = self.0.to_le_bytes();
+        [status, status2, status3]
+    }
+
+    pub const fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    /// All flags of `other` are set
+    pub const fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Any flag of `other` is set
+    pub const fn intersects(self, other: Self) -> bool {
+        self.0 & other.0 != 0
+    }
+
+    /// AGC ready interrupt
+    pub fn agc_ready(self) -> bool {
+        self.intersects(Self::AGC_READY)
+    }
+
+    /// The FIFO is full
+    pub fn fifo_full(self) -> bool {
+        self.intersects(Self::FIFO_FULL)
+    }
+
+    /// The FIFO reached the watermark
+    pub fn fifo_threshold(self) -> bool {
+        self.intersects(Self::FIFO_THRESHOLD)
+    }
+
+    /// New data is available in the data registers
+    pub fn data_ready(self) -> bool {
+        self.intersects(Self::DATA_READY)
+    }
+
+    /// Software reset is complete
+    pub fn reset_done(self) -> bool {
+        self.intersects(Self::RESET_DONE)
+    }
+
+    /// PLL ready interrupt
+    pub fn pll_ready(self) -> bool {
+        self.intersects(Self::PLL_READY)
+    }
+
+    /// UI FSYNC interrupt
+    pub fn ui_fsync(self) -> bool {
+        self.intersects(Self::UI_FSYNC)
+    }
+
+    /// Wake on motion on any axis
+    pub fn wake_on_motion(self) -> bool {
+        self.intersects(Self::WOM)
+    }
+
+    /// Significant motion detected
+    pub fn significant_motion(self) -> bool {
+        self.intersects(Self::SMD)
+    }
+
+    /// Tap detected
+    pub fn tap(self) -> bool {
+        self.intersects(Self::TAP)
+    }
+
+    /// Raise to wake/sleep: sleep event
+    pub fn sleep(self) -> bool {
+        self.intersects(Self::SLEEP)
+    }
+
+    /// Raise to wake/sleep: wake event
+    pub fn wake(self) -> bool {
+        self.intersects(Self::WAKE)
+    }
+
+    /// Tilt detected
+    pub fn tilt(self) -> bool {
+        self.intersects(Self::TILT)
+    }
+
+    /// The pedometer step count overflowed
+    pub fn step_count_overflow(self) -> bool {
+        self.intersects(Self::STEP_COUNT_OVERFLOW)
+    }
+
+    /// Step detected
+    pub fn step(self) -> bool {
+        self.intersects(Self::STEP)
+    }
+}
+
+impl BitOr for Interrupts {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        Interrupts(self.0 | rhs.0)
+    }
+}
+
+impl BitOrAssign for Interrupts {
+    fn bitor_assign(&mut self, rhs: Self) {
+        self.0 |= rhs.0;
+    }
+}
+
+impl BitAnd for Interrupts {
+    type Output = Self;
+
+    fn bitand(self, rhs: Self) -> Self {
+        Interrupts(self.0 & rhs.0)
+    }
+}
+
+/// Interrupt output pin
+#[derive(Clone, Copy, Debug, PartialEq, Eq, defmt::Format)]
+pub enum InterruptPin {
+    Int1,
+    /// Only available if pin 9 is configured as
+    /// [`Pin9Function::INT2`](crate::config::Pin9Function::INT2)
+    Int2,
+}
+
+/// `INT_SOURCE` registers of a pin, in the order of the status registers
+struct SourceRegisters {
+    status: u8,
+    status2: u8,
+    /// In bank 4
+    status3: u8,
+}
+
+impl InterruptPin {
+    fn source_registers(self) -> SourceRegisters {
+        match self {
+            InterruptPin::Int1 => SourceRegisters {
+                status: INT_SOURCE0::ID,
+                status2: INT_SOURCE1::ID,
+                status3: INT_SOURCE6::ID,
+            },
+            InterruptPin::Int2 => SourceRegisters {
+                status: INT_SOURCE3::ID,
+                status2: INT_SOURCE4::ID,
+                status3: INT_SOURCE7::ID,
+            },
+        }
+    }
+}
+
+/// Address of `INT_STATUS`
+const INT_STATUS_ADDR: u8 = INT_STATUS::ID;
+
+/// Address of `INT_STATUS2`, followed by `INT_STATUS3`
+const INT_STATUS2_ADDR: u8 = INT_STATUS2::ID;
+
+impl<BUS, PIN> ICM42688<BUS, Ready<PIN>> {
+    /// Read and clear the interrupt status registers
+    #[cfg(feature = "async")]
+    pub async fn async_read_interrupt_status(
+        &mut self,
+    ) -> Result<InterruptStatus, Error<BUS::Error>>
+    where
+        BUS: AsyncRegisterInterface,
+    {
+        self.ll.try_bank::<0>()?;
+        let mut registers = [0; 3];
+        self.ll
+            .bus
+            .read_regs(INT_STATUS_ADDR, &mut registers[..1])
+            .await
+            .map_err(Error::Bus)?;
+        self.ll
+            .bus
+            .read_regs(INT_STATUS2_ADDR, &mut registers[1..])
+            .await
+            .map_err(Error::Bus)?;
+        Ok(self.track_status(InterruptStatus::from_registers(registers)))
+    }
+
+    /// Read and clear the interrupt status registers
+    #[cfg(feature = "blocking")]
+    pub fn read_interrupt_status(&mut self) -> Result<InterruptStatus, Error<BUS::Error>>
+    where
+        BUS: RegisterInterface,
+    {
+        self.ll.try_bank::<0>()?;
+        let mut registers = [0; 3];
+        self.ll
+            .bus
+            .read_regs(INT_STATUS_ADDR, &mut registers[..1])
+            .map_err(Error::Bus)?;
+        self.ll
+            .bus
+            .read_regs(INT_STATUS2_ADDR, &mut registers[1..])
+            .map_err(Error::Bus)?;
+        Ok(self.track_status(InterruptStatus::from_registers(registers)))
+    }
+
+    /// Update the driver state from flags that are cleared by reading them
+    fn track_status(&mut self, status: InterruptStatus) -> InterruptStatus {
+        if status.step_count_overflow() {
+            self.state.inner.step_counter.overflowed();
+        }
+        status
+    }
+
+    /// Route `sources` to `pin`, in addition to the sources already routed
+    #[cfg(feature = "async")]
+    pub async fn async_enable_interrupts(
+        &mut self,
+        pin: InterruptPin,
+        sources: Interrupts,
+    ) -> Result<(), Error<BUS::Error>>
+    where
+        BUS: AsyncRegisterInterface,
+    {
+        self.async_route_interrupts(pin, sources, true).await
+    }
+
+    /// Route `sources` to `pin`, in addition to the sources already routed
+    #[cfg(feature = "blocking")]
+    pub fn enable_interrupts(
+        &mut self,
+        pin: InterruptPin,
+        sources: Interrupts,
+    ) -> Result<(), Error<BUS::Error>>
+    where
+        BUS: RegisterInterface,
+    {
+        self.route_interrupts(pin, sources, true)
+    }
+
+    /// Stop routing `sources` to `pin`
+    #[cfg(feature = "async")]
+    pub async fn async_disable_interrupts(
+        &mut self,
+        pin: InterruptPin,
+        sources: Interrupts,
+    ) -> Result<(), Error<BUS::Error>>
+    where
+        BUS: AsyncRegisterInterface,
+    {
+        self.async_route_interrupts(pin, sources, false).await
+    }
+
+    /// Stop routing `sources` to `pin`
+    #[cfg(feature = "blocking")]
+    pub fn disable_interrupts(
+        &mut self,
+        pin: InterruptPin,
+        sources: Interrupts,
+    ) -> Result<(), Error<BUS::Error>>
+    where
+        BUS: RegisterInterface,
+    {
+        self.route_interrupts(pin, sources, false)
+    }
+
+    #[cfg(feature = "async")]
+    async fn async_route_interrupts(
+        &mut self,
+        pin: InterruptPin,
+        sources: Interrupts,
+        enable: bool,
+    ) -> Result<(), Error<BUS::Error>>
+    where
+        BUS: AsyncRegisterInterface,
+    {
+        let (bank0_mutations, bank4_mutation) = routing_mutations(pin, sources, enable);
+        let mut bank0 = self.ll.try_bank::<0>()?;
+        for mutation in &bank0_mutations {
+            bank0.async_apply_mutations(mutation.as_slice()).await?;
+        }
+
+        // Only switch banks if APEX sources are routed
+        if let Some(mutation) = bank4_mutation {
+            self.async_select_bank(BANK4).await?;
+            self.ll
+                .try_bank::<4>()?
+                .async_apply_mutations(&[mutation])
+                .await?;
+            self.async_select_bank(BANK0).await?;
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "blocking")]
+    fn route_interrupts(
+        &mut self,
+        pin: InterruptPin,
+        sources: Interrupts,
+        enable: bool,
+    ) -> Result<(), Error<BUS::Error>>
+    where
+        BUS: RegisterInterface,
+    {
+        let (bank0_mutations, bank4_mutation) = routing_mutations(pin, sources, enable);
+        let mut bank0 = self.ll.try_bank::<0>()?;
+        for mutation in &bank0_mutations {
+            bank0.apply_mutations(mutation.as_slice())?;
+        }
+
+        // Only switch banks if APEX sources are routed
+        if let Some(mutation) = bank4_mutation {
+            self.select_bank(BANK4)?;
+            self.ll.try_bank::<4>()?.apply_mutations(&[mutation])?;
+            self.select_bank(BANK0)?;
+        }
+        Ok(())
+    }
+}
+
+impl<BUS> ICM42688<BUS, Ready> {
+    /// Attach the MCU pin connected to `line`
+    ///
+    /// Enables [`wait_for_event`](ICM42688::wait_for_event) and friends when
+    /// `pin` implements [`Wait`](embedded_hal_async::digital::Wait).
+    pub fn with_int_pin<PIN>(self, pin: PIN, line: InterruptPin) -> ICM42688<BUS, Ready<PIN>> {
+        ICM42688 {
+            ll: self.ll,
+            state: Ready {
+                inner: self.state.inner,
+                int_pin: pin,
+                int_pin_line: line,
+            },
+        }
+    }
+}
+
+impl<BUS, PIN> ICM42688<BUS, Ready<PIN>> {
+    /// Detach the interrupt pin
+    pub fn release_int_pin(self) -> (ICM42688<BUS, Ready>, PIN) {
+        let icm = ICM42688 {
+            ll: self.ll,
+            state: Ready {
+                inner: self.state.inner,
+                int_pin: NoPin,
+                int_pin_line: InterruptPin::Int1,
+            },
+        };
+        (icm, self.state.int_pin)
+    }
+}
+
+#[cfg(feature = "async")]
+impl<BUS, PIN> ICM42688<BUS, Ready<PIN>>
+where
+    PIN: Wait,
+{
+    /// Electrical configuration of the line the interrupt pin is attached to
+    fn int_pin_config(&self) -> IntPin {
+        match self.state.int_pin_line {
+            InterruptPin::Int1 => self.state.inner.int1,
+            InterruptPin::Int2 => self.state.inner.int2,
+        }
+    }
+
+    /// Wait for the interrupt pin, then read and clear the status registers
+    ///
+    /// In latched mode this waits for the active level, so an interrupt that
+    /// is already pending returns immediately. In pulsed mode it waits for the
+    /// next active edge. The returned status may be empty if the pin was
+    /// asserted by a source that is cleared by reading the data (see
+    /// [`DataMode`](crate::config::DataMode)) and the data wasn't read yet.
+    pub async fn wait_for_event(&mut self) -> Result<InterruptStatus, Error<BUS::Error>>
+    where
+        BUS: AsyncRegisterInterface,
+    {
+        let config = self.int_pin_config();
+        let pin = &mut self.state.int_pin;
+        match (config.mode, config.polarity) {
+            (IntMode::Latched, Polarity::ActiveHigh) => pin.wait_for_high().await,
+            (IntMode::Latched, Polarity::ActiveLow) => pin.wait_for_low().await,
+            (IntMode::Pulsed, Polarity::ActiveHigh) => pin.wait_for_rising_edge().await,
+            (IntMode::Pulsed, Polarity::ActiveLow) => pin.wait_for_falling_edge().await,
+        }
+        .map_err(|_| Error::Pin)?;
+        self.async_read_interrupt_status().await
+    }
+
+    /// Wait until the FIFO reached the watermark or is full
+    ///
+    /// The returned status also holds the other events seen while waiting. Returns
+    /// [`Error::WrongDataMode`] in [`DataMode::DataReady`](crate::config::DataMode::DataReady).
+    pub async fn wait_for_fifo_watermark(&mut self) -> Result<InterruptStatus, Error<BUS::Error>>
+    where
+        BUS: AsyncRegisterInterface,
+    {
+        self.check_fifo_data_mode()?;
+        self.wait_for(Interrupts::FIFO_THRESHOLD | Interrupts::FIFO_FULL)
+            .await
+    }
+
+    /// Wait until new data is available in the data registers
+    ///
+    /// The returned status also holds the other events seen while waiting. Returns
+    /// [`Error::WrongDataMode`] in [`DataMode::FifoWatermark`](crate::config::DataMode::FifoWatermark).
+    pub async fn wait_for_data_ready(&mut self) -> Result<InterruptStatus, Error<BUS::Error>>
+    where
+        BUS: AsyncRegisterInterface,
+    {
+        self.check_data_ready_mode()?;
+        self.wait_for(Interrupts::DATA_READY).await
+    }
+
+    async fn wait_for(&mut self, sources: Interrupts) -> Result<InterruptStatus, Error<BUS::Error>>
+    where
+        BUS: AsyncRegisterInterface,
+    {
+        // Most status bits are cleared on read, keep the ones seen while waiting
+        let mut seen = Interrupts::empty();
+        loop {
+            let status = self.wait_for_event().await?;
+            seen |= status;
+            if status.intersects(sources) {
+                return Ok(seen);
+            }
+        }
+    }
+}
+
+/// Mutations of the `INT_SOURCE` registers, registers without any of
+/// `sources` are skipped
+#[allow(clippy::type_complexity)]
+fn routing_mutations(
+    pin: InterruptPin,
+    sources: Interrupts,
+    enable: bool,
+) -> ([Option<Mutation<BANK0>>; 2], Option<Mutation<BANK4>>) {
+    let registers = pin.source_registers();
+    let [status, status2, status3] = sources.to_registers();
+    let mutation =
+        |register_id, mask| (mask != 0).then(|| Mutation::bits(register_id, mask, enable));
+    (
+        [
+            mutation(registers.status, status),
+            mutation(registers.status2, status2),
+        ],
+        (status3 != 0).then(|| Mutation::bits(registers.status3, status3, enable)),
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_status_registers() {
+        let status = InterruptStatus::from_registers([0b0000_0100, 0b0000_1010, 0b1100_0001]);
+        assert!(status.fifo_threshold());
+        assert!(!status.data_ready());
+        assert!(status.contains(Interrupts::WOM_Y | Interrupts::SMD));
+        assert!(status.wake_on_motion());
+        assert!(status.tap());
+        assert!(!status.step());
+        // Reserved bits are dropped
+        assert_eq!(
+            status.to_registers(),
+            [0b0000_0100, 0b0000_1010, 0b0000_0001]
+        );
+    }
+}
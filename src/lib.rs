@@ -1,21 +1,96 @@
 #![no_std]
 #![cfg_attr(not(doctest), doc = include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/README.md")))]
+// Only report the missing feature, not the code it leaves unused
+#![cfg_attr(not(any(feature = "async", feature = "blocking")), allow(unused))]
 
+#[cfg(not(any(feature = "async", feature = "blocking")))]
+compile_error!("At least one of the `async` and `blocking` features has to be enabled");
+
+pub mod apex;
+pub mod clock_sync;
 pub mod config;
+mod data;
+mod error;
 pub mod fifo;
+pub mod interface;
+pub mod interrupt;
 pub mod ll;
+pub mod motion;
 pub mod ready;
 pub mod register_bank;
+pub mod sample;
+pub mod timestamp;
 pub mod uninitialized;
 
 pub use config::Config;
+pub use error::Error;
+#[cfg(feature = "async")]
+pub use interface::AsyncRegisterInterface;
+#[cfg(feature = "blocking")]
+pub use interface::RegisterInterface;
 
 #[derive(Debug)]
 pub struct Uninitialized;
 
 /// Indicates that the `ICM42688` instance is ready to be used
-#[derive(Debug)]
-pub struct Ready;
+///
+/// Keeps track of the settings that are needed to interpret the data. `PIN`
+/// is the interrupt pin attached with
+/// [`with_int_pin`](ICM42688::with_int_pin), if any.
+#[derive(Debug, Clone, Copy)]
+pub struct Ready<PIN = NoPin> {
+    inner: ReadyState,
+    int_pin: PIN,
+    /// Only needed to wait for the pin
+    #[cfg_attr(not(feature = "async"), allow(dead_code))]
+    int_pin_line: interrupt::InterruptPin,
+}
+
+/// State of [`Ready`] that doesn't depend on the interrupt pin
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct ReadyState {
+    gyro_full_scale: config::GyroFullScale,
+    accel_full_scale: config::AccelFullScale,
+    accel_odr: config::AccelOdr,
+    fifo: config::Fifo,
+    timestamp_tracker: timestamp::TimestampTracker,
+    sensor_data_endian: config::Endian,
+    data_mode: config::DataMode,
+    /// Only needed to wait for the pin
+    #[cfg_attr(not(feature = "async"), allow(dead_code))]
+    int1: config::Int1,
+    #[cfg_attr(not(feature = "async"), allow(dead_code))]
+    int2: config::Int2,
+    step_counter: apex::StepCounter,
+    dmp_odr: config::DmpOdr,
+}
+
+impl Ready {
+    pub(crate) fn new(config: &Config) -> Self {
+        Ready {
+            inner: ReadyState {
+                gyro_full_scale: config.gyro.full_scale,
+                accel_full_scale: config.accel.full_scale,
+                accel_odr: config.accel.odr,
+                fifo: config.fifo,
+                timestamp_tracker: timestamp::TimestampTracker::new(config),
+                // Always selected by `initialize`
+                sensor_data_endian: config::Endian::Big,
+                data_mode: config.data_mode,
+                int1: config.int1,
+                int2: config.int2,
+                step_counter: Default::default(),
+                dmp_odr: config.apex.dmp_odr,
+            },
+            int_pin: NoPin,
+            int_pin_line: interrupt::InterruptPin::Int1,
+        }
+    }
+}
+
+/// No interrupt pin is attached to the driver
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoPin;
 
 /// ICM42688 top-level driver
 ///
@@ -34,28 +109,113 @@ pub struct Ready;
 ///     let spidev =
 ///         embedded_hal_bus::spi::ExclusiveDevice::new_no_delay(spi, pin.clone()).unwrap();
 ///     let mut icm = icm426xx::ICM42688::new(spidev);
-///     let mut icm = icm.initialize(Delay).await.unwrap();
+///     let mut icm = icm.async_initialize(Delay, Default::default()).await.unwrap();
 ///     let mut bank = icm.ll().bank::<{ icm426xx::register_bank::BANK0 }>();
 ///
 ///     // print WHO_AM_I register
 ///     let who_am_i = bank.who_am_i().async_read().await;
 ///     loop {
-///         let fifo_count = icm.read_fifo_count().await;
-///         let mut fifo_buffer = [0u32; 128];
-///         let num_read = icm.read_fifo(&mut fifo_buffer).await.unwrap();
+///         let fifo_count = icm.async_read_fifo_count().await.unwrap();
+///         let mut fifo_buffer = [0u8; 512];
+///         let result = icm.async_read_fifo_into(&mut fifo_buffer).await.unwrap();
+///         for packet in icm426xx::fifo::FifoParser::new(&fifo_buffer[..result.bytes_read]) {
+///             let sample = icm426xx::sample::Sample::from_packet(&packet, icm.full_scale());
+///         }
 ///     }
 /// }
 /// ```
-pub struct ICM42688<SPI, State> {
-    ll: crate::ll::ICM42688<SPI>,
-    _state: State,
+pub struct ICM42688<BUS, State> {
+    ll: crate::ll::ICM42688<BUS>,
+    state: State,
+}
+
+/// Helpers shared by the async and blocking tests
+#[cfg(test)]
+mod test_util {
+    use embedded_hal_mock::eh1::i2c;
+    extern crate std;
+    use std::{vec, vec::Vec};
+
+    use crate::interface::{I2cInterface, I2C_ADDRESS_AD0_LOW as ADDR};
+
+    pub(crate) type MockIcm<'a> = super::ICM42688<I2cInterface<&'a mut i2c::Mock>, super::Ready>;
+
+    /// A driver on `i2c` in the state `initialize` leaves it in, without the
+    /// bus traffic of the initialization
+    pub(crate) fn ready<'a>(i2c: &'a mut i2c::Mock, config: &super::Config) -> MockIcm<'a> {
+        super::ICM42688 {
+            ll: crate::ll::ICM42688::new(I2cInterface::new(i2c, ADDR)),
+            state: super::Ready::new(config),
+        }
+    }
+
+    /// Register writes of `initialize` with the default config after the
+    /// chip id check, in order
+    const INIT_WRITES: [(u8, u8); 28] = [
+        (20, 54),
+        (22, 192),
+        (76, 50),
+        (77, 64),
+        (79, 6),
+        (80, 6),
+        (81, 0),
+        (82, 0),
+        (83, 0),
+        (84, 29),
+        (95, 119),
+        (96, 0),
+        (97, 0),
+        (99, 8),
+        (100, 0),
+        (101, 4),
+        (REG_BANK_SEL, 1),
+        (11, 0),
+        (12, 13),
+        (13, 170),
+        (14, 128),
+        (123, 0),
+        (REG_BANK_SEL, 2),
+        (3, 26),
+        (4, 170),
+        (5, 128),
+        (REG_BANK_SEL, 0),
+        (78, 15),
+    ];
+
+    const REG_BANK_SEL: u8 = 118;
+
+    /// The I2C traffic of `initialize`, with the written values of the
+    /// registers in `overrides` replaced
+    pub(crate) fn init_transactions(overrides: &[(u8, u8)]) -> Vec<i2c::Transaction> {
+        let mut transactions = vec![
+            i2c::Transaction::write_read(ADDR, vec![17], vec![0]),
+            i2c::Transaction::write(ADDR, vec![17, 1]),
+            i2c::Transaction::write_read(ADDR, vec![117], vec![0x47]),
+        ];
+        for (reg, default) in INIT_WRITES {
+            if reg == REG_BANK_SEL {
+                transactions.push(i2c::Transaction::write(ADDR, vec![reg, default]));
+                continue;
+            }
+            let value = overrides
+                .iter()
+                .find(|(r, _)| *r == reg)
+                .map_or(default, |(_, v)| *v);
+            transactions.push(i2c::Transaction::write_read(ADDR, vec![reg], vec![0]));
+            transactions.push(i2c::Transaction::write(ADDR, vec![reg, value]));
+        }
+        transactions
+    }
 }
 
 #[cfg(all(test, feature = "async"))]
 mod test {
-    use embedded_hal_mock::eh1::{delay::NoopDelay, spi};
+    use embedded_hal_mock::eh1::{delay::NoopDelay, i2c, spi};
     extern crate std;
-    use std::vec;
+    use std::{vec, vec::Vec};
+
+    use super::test_util::{init_transactions, ready};
+    use crate::interface::{I2cInterface, I2C_ADDRESS_AD0_LOW as ADDR};
 
     #[async_std::test]
     async fn test_init0() {
@@ -73,7 +233,7 @@ mod test {
             spi::Transaction::transfer_in_place(vec![148, 0], vec![0, 0]),
             spi::Transaction::transaction_end(),
             spi::Transaction::transaction_start(),
-            spi::Transaction::write_vec(vec![20, 6]),
+            spi::Transaction::write_vec(vec![20, 54]),
             spi::Transaction::transaction_end(),
             spi::Transaction::transaction_start(),
             spi::Transaction::transfer_in_place(vec![150, 0], vec![0, 0]),
@@ -133,7 +293,7 @@ mod test {
             spi::Transaction::transfer_in_place(vec![223, 0], vec![0, 0]),
             spi::Transaction::transaction_end(),
             spi::Transaction::transaction_start(),
-            spi::Transaction::write_vec(vec![95, 55]),
+            spi::Transaction::write_vec(vec![95, 119]),
             spi::Transaction::transaction_end(),
             spi::Transaction::transaction_start(),
             spi::Transaction::transfer_in_place(vec![224, 0], vec![0, 0]),
@@ -230,7 +390,10 @@ mod test {
             spi::Transaction::transaction_end(),
         ]);
         let icm = super::ICM42688::new(&mut spi);
-        let _icm = icm.initialize(NoopDelay, Default::default()).await.unwrap();
+        let _icm = icm
+            .async_initialize(NoopDelay, Default::default())
+            .await
+            .unwrap();
         spi.done();
     }
 
@@ -250,7 +413,7 @@ mod test {
             spi::Transaction::transfer_in_place(vec![148, 0], vec![0xff, 0xff]),
             spi::Transaction::transaction_end(),
             spi::Transaction::transaction_start(),
-            spi::Transaction::write_vec(vec![20, 254]),
+            spi::Transaction::write_vec(vec![20, 246]),
             spi::Transaction::transaction_end(),
             spi::Transaction::transaction_start(),
             spi::Transaction::transfer_in_place(vec![150, 0], vec![0xff, 0xff]),
@@ -262,7 +425,7 @@ mod test {
             spi::Transaction::transfer_in_place(vec![204, 0], vec![0xff, 0xff]),
             spi::Transaction::transaction_end(),
             spi::Transaction::transaction_start(),
-            spi::Transaction::write_vec(vec![76, 255]),
+            spi::Transaction::write_vec(vec![76, 191]),
             spi::Transaction::transaction_end(),
             spi::Transaction::transaction_start(),
             spi::Transaction::transfer_in_place(vec![205, 0], vec![0xff, 0xff]),
@@ -407,7 +570,730 @@ mod test {
             spi::Transaction::transaction_end(),
         ]);
         let icm = super::ICM42688::new(&mut spi);
-        let _icm = icm.initialize(NoopDelay, Default::default()).await.unwrap();
+        let _icm = icm
+            .async_initialize(NoopDelay, Default::default())
+            .await
+            .unwrap();
         spi.done();
     }
+
+    #[async_std::test]
+    async fn test_init0_i2c() {
+        let mut i2c = i2c::Mock::new(&init_transactions(&[]));
+        let icm = super::ICM42688::new(I2cInterface::new(&mut i2c, ADDR));
+        let _icm = icm
+            .async_initialize(NoopDelay, Default::default())
+            .await
+            .unwrap();
+        i2c.done();
+    }
+
+    #[async_std::test]
+    async fn test_init_data_ready_i2c() {
+        let mut i2c = i2c::Mock::new(&init_transactions(&[(22, 0), (99, 32), (101, 8)]));
+        let icm = super::ICM42688::new(I2cInterface::new(&mut i2c, ADDR));
+        let mut config = crate::Config::default();
+        config.data_mode = crate::config::DataMode::DataReady;
+        let _icm = icm.async_initialize(NoopDelay, config).await.unwrap();
+        i2c.done();
+    }
+
+    #[async_std::test]
+    async fn test_init_full_scale_i2c() {
+        let mut i2c = i2c::Mock::new(&init_transactions(&[(79, 0b011_00110), (80, 0b011_00110)]));
+        let icm = super::ICM42688::new(I2cInterface::new(&mut i2c, ADDR));
+        let mut config = crate::Config::default();
+        config.gyro.full_scale = crate::config::GyroFullScale::_250Dps;
+        config.accel.full_scale = crate::config::AccelFullScale::_2G;
+        let icm = icm.async_initialize(NoopDelay, config).await.unwrap();
+        assert_eq!(icm.gyro_full_scale(), crate::config::GyroFullScale::_250Dps);
+        assert_eq!(icm.accel_full_scale(), crate::config::AccelFullScale::_2G);
+        i2c.done();
+    }
+
+    #[async_std::test]
+    async fn test_read_fifo_i2c() {
+        let mut response = vec![0x04, 0x00, 0x14];
+        response.extend((0..24).map(|i| i as u8));
+        let mut i2c = i2c::Mock::new(&[i2c::Transaction::write_read(ADDR, vec![0x2D], response)]);
+        let mut icm = ready(&mut i2c, &Default::default());
+        let mut buffer = [0u32; 7];
+        let num_read = icm.async_read_fifo(&mut buffer).await.unwrap();
+        assert_eq!(num_read, 1);
+
+        let bytes = bytemuck::cast_slice::<u32, u8>(&buffer);
+        assert_eq!(&bytes[1..4], &[0x04, 0x00, 0x14]);
+        assert_eq!(bytes[4], 0);
+        assert_eq!(bytes[27], 23);
+        i2c.done();
+    }
+
+    #[async_std::test]
+    async fn test_init_wrong_chip_id() {
+        let mut i2c = i2c::Mock::new(&[
+            i2c::Transaction::write_read(ADDR, vec![17], vec![0]),
+            i2c::Transaction::write(ADDR, vec![17, 1]),
+            i2c::Transaction::write_read(ADDR, vec![117], vec![0x12]),
+        ]);
+        let icm = super::ICM42688::new(I2cInterface::new(&mut i2c, ADDR));
+        let result = icm.async_initialize(NoopDelay, Default::default()).await;
+        assert!(matches!(result, Err(super::Error::WrongChipId(0x12))));
+        i2c.done();
+    }
+
+    #[async_std::test]
+    async fn test_read_fifo_count_bus_error() {
+        let mut i2c = i2c::Mock::new(&[i2c::Transaction::write_read(ADDR, vec![0x2E], vec![0])
+            .with_error(embedded_hal::i2c::ErrorKind::Other)]);
+        let mut icm = ready(&mut i2c, &Default::default());
+        let result = icm.async_read_fifo_count().await;
+        assert_eq!(
+            result,
+            Err(super::Error::Bus(embedded_hal::i2c::ErrorKind::Other))
+        );
+        i2c.done();
+    }
+
+    #[async_std::test]
+    async fn test_read_fifo_into_i2c() {
+        // Two packets 4 in the FIFO, the buffer only fits one
+        let mut packet = vec![0x78];
+        packet.extend((1..20).map(|i| i as u8));
+        let mut i2c = i2c::Mock::new(&[
+            i2c::Transaction::write_read(ADDR, vec![0x2D], vec![0x04, 0x00, 0x28]),
+            i2c::Transaction::write_read(ADDR, vec![0x30], packet.clone()),
+        ]);
+        let mut icm = ready(&mut i2c, &Default::default());
+        let mut buffer = [0u8; 31];
+        let result = icm.async_read_fifo_into(&mut buffer[1..]).await.unwrap();
+        assert!(result.int_status.fifo_threshold());
+        assert_eq!(result.fifo_count, 40);
+        assert_eq!(result.bytes_read, 20);
+        assert_eq!(result.packets, 1);
+        assert!(result.data_left);
+        assert_eq!(&buffer[1..21], &packet[..]);
+        i2c.done();
+    }
+
+    #[async_std::test]
+    async fn test_read_fifo_into_too_small() {
+        let mut i2c = i2c::Mock::new(&[i2c::Transaction::write_read(
+            ADDR,
+            vec![0x2D],
+            vec![0x00, 0x00, 0x14],
+        )]);
+        let mut icm = ready(&mut i2c, &Default::default());
+        let mut buffer = [0u8; 19];
+        let result = icm.async_read_fifo_into(&mut buffer).await;
+        assert!(matches!(result, Err(super::Error::BufferTooSmall)));
+        i2c.done();
+    }
+
+    #[async_std::test]
+    async fn test_drain_fifo_i2c() {
+        // Three packets 4 in the FIFO, the buffer fits two
+        let packets: Vec<Vec<u8>> = (0..3)
+            .map(|n| {
+                let mut packet = vec![0x78];
+                packet.extend((1..20).map(|i| n * 20 + i));
+                packet
+            })
+            .collect();
+        let mut i2c = i2c::Mock::new(&[
+            i2c::Transaction::write_read(ADDR, vec![0x2E], vec![0x00]),
+            i2c::Transaction::write_read(ADDR, vec![0x2F], vec![0x3C]),
+            i2c::Transaction::write_read(ADDR, vec![0x30], packets[..2].concat()),
+            i2c::Transaction::write_read(ADDR, vec![0x30], packets[2].clone()),
+        ]);
+        let mut icm = ready(&mut i2c, &Default::default());
+        let mut buffer = [0u8; 50];
+        let mut chunks = Vec::new();
+        let drained = icm
+            .async_drain_fifo(&mut buffer, |chunk| chunks.push(chunk.to_vec()))
+            .await
+            .unwrap();
+        assert_eq!(drained, 60);
+        assert_eq!(chunks, vec![packets[..2].concat(), packets[2].clone()]);
+        i2c.done();
+    }
+
+    #[async_std::test]
+    async fn test_read_fifo_into_records_i2c() {
+        // Two records of 16 bytes (packet 3)
+        let mut packets = vec![0x60];
+        packets.extend(1..16);
+        packets.push(0x60);
+        packets.extend(17..32);
+        let mut i2c = i2c::Mock::new(&[
+            i2c::Transaction::write_read(ADDR, vec![0x2D], vec![0x00, 0x00, 0x02]),
+            i2c::Transaction::write_read(ADDR, vec![0x30], packets),
+        ]);
+        let mut config = crate::Config::default();
+        config.fifo.high_resolution = false;
+        config.fifo.count_records = true;
+        let mut icm = ready(&mut i2c, &config);
+        let mut buffer = [0u8; 64];
+        let result = icm.async_read_fifo_into(&mut buffer).await.unwrap();
+        assert_eq!(result.fifo_count, 32);
+        assert_eq!(result.bytes_read, 32);
+        assert_eq!(result.packets, 2);
+        assert!(!result.data_left);
+        i2c.done();
+    }
+
+    #[async_std::test]
+    async fn test_read_all_i2c() {
+        let mut i2c = i2c::Mock::new(&[
+            i2c::Transaction::write_read(
+                ADDR,
+                vec![0x1D],
+                vec![0, 0, 0x08, 0, 0, 0, 0xF8, 0, 0, 0, 0, 0, 0x80, 0],
+            ),
+            i2c::Transaction::write_read(ADDR, vec![0x25], vec![0, 164, 0, 0, 0, 0]),
+        ]);
+        let mut icm = ready(&mut i2c, &Default::default());
+        let sample = icm.async_read_all().await.unwrap();
+        let accel = sample.accel_g().unwrap();
+        assert!((accel[0] - 1.0).abs() < 1e-6);
+        assert!((accel[2] + 1.0).abs() < 1e-6);
+        assert_eq!(sample.gyro, None);
+        assert!((sample.temperature - 25.0).abs() < 1e-6);
+
+        let gyro = icm.async_read_gyro().await.unwrap().unwrap();
+        assert!((gyro[0] - 10f32.to_radians()).abs() < 1e-6);
+        i2c.done();
+    }
+
+    #[async_std::test]
+    async fn test_read_fifo_data_ready_mode() {
+        let mut i2c = i2c::Mock::new(&[]);
+        let mut config = crate::Config::default();
+        config.data_mode = crate::config::DataMode::DataReady;
+        let mut icm = ready(&mut i2c, &config);
+        let mut buffer = [0u8; 64];
+        let result = icm.async_read_fifo_into(&mut buffer).await;
+        assert_eq!(result, Err(super::Error::WrongDataMode));
+        i2c.done();
+    }
+
+    #[async_std::test]
+    async fn test_interrupts_i2c() {
+        use crate::interrupt::{InterruptPin, Interrupts};
+
+        let mut i2c = i2c::Mock::new(&[
+            // INT_SOURCE4: WOM on all axes
+            i2c::Transaction::write_read(ADDR, vec![0x69], vec![0x40]),
+            i2c::Transaction::write(ADDR, vec![0x69, 0x47]),
+            // Bank 4 INT_SOURCE7: tap
+            i2c::Transaction::write(ADDR, vec![0x76, 4]),
+            i2c::Transaction::write_read(ADDR, vec![0x4E], vec![0x00]),
+            i2c::Transaction::write(ADDR, vec![0x4E, 0x01]),
+            i2c::Transaction::write(ADDR, vec![0x76, 0]),
+            // INT_STATUS, INT_STATUS2 and INT_STATUS3
+            i2c::Transaction::write_read(ADDR, vec![0x2D], vec![0x08]),
+            i2c::Transaction::write_read(ADDR, vec![0x37], vec![0x02, 0x01]),
+        ]);
+        let mut icm = ready(&mut i2c, &Default::default());
+        icm.async_enable_interrupts(InterruptPin::Int2, Interrupts::WOM | Interrupts::TAP)
+            .await
+            .unwrap();
+        let status = icm.async_read_interrupt_status().await.unwrap();
+        assert_eq!(
+            status,
+            Interrupts::DATA_READY | Interrupts::WOM_Y | Interrupts::TAP
+        );
+        i2c.done();
+    }
+
+    #[async_std::test]
+    async fn test_wait_for_data_ready_i2c() {
+        use embedded_hal_mock::eh1::digital::{self, State};
+
+        let mut config = crate::Config::default();
+        config.data_mode = crate::config::DataMode::DataReady;
+        let mut i2c = i2c::Mock::new(&[
+            i2c::Transaction::write_read(ADDR, vec![0x2D], vec![0x08]),
+            i2c::Transaction::write_read(ADDR, vec![0x37], vec![0x00, 0x00]),
+        ]);
+        let mut pin = digital::Mock::new(&[digital::Transaction::wait_for_state(State::Low)]);
+        let icm = ready(&mut i2c, &config);
+        let mut icm = icm.with_int_pin(pin.clone(), crate::interrupt::InterruptPin::Int1);
+        let status = icm.wait_for_data_ready().await.unwrap();
+        assert!(status.data_ready());
+        let (_icm, _pin) = icm.release_int_pin();
+        pin.done();
+        i2c.done();
+    }
+
+    #[async_std::test]
+    async fn test_wait_for_data_ready_fifo_mode_i2c() {
+        use embedded_hal_mock::eh1::digital;
+
+        let mut i2c = i2c::Mock::new(&[]);
+        let mut pin = digital::Mock::new(&[]);
+        let icm = ready(&mut i2c, &Default::default());
+        let mut icm = icm.with_int_pin(pin.clone(), crate::interrupt::InterruptPin::Int1);
+        let result = icm.wait_for_data_ready().await;
+        assert_eq!(result, Err(super::Error::WrongDataMode));
+        let (_icm, _pin) = icm.release_int_pin();
+        pin.done();
+        i2c.done();
+    }
+
+    #[async_std::test]
+    async fn test_wait_for_fifo_watermark_i2c() {
+        use embedded_hal_mock::eh1::digital::{self, State};
+
+        let mut i2c = i2c::Mock::new(&[
+            // Only data ready and wake on motion, keep waiting
+            i2c::Transaction::write_read(ADDR, vec![0x2D], vec![0x08]),
+            i2c::Transaction::write_read(ADDR, vec![0x37], vec![0x01, 0x00]),
+            i2c::Transaction::write_read(ADDR, vec![0x2D], vec![0x04]),
+            i2c::Transaction::write_read(ADDR, vec![0x37], vec![0x00, 0x00]),
+        ]);
+        let mut pin = digital::Mock::new(&[
+            digital::Transaction::wait_for_state(State::Low),
+            digital::Transaction::wait_for_state(State::Low),
+        ]);
+        let icm = ready(&mut i2c, &Default::default());
+        let mut icm = icm.with_int_pin(pin.clone(), crate::interrupt::InterruptPin::Int1);
+        let status = icm.wait_for_fifo_watermark().await.unwrap();
+        assert!(status.fifo_threshold());
+        assert!(status.data_ready());
+        assert!(status.wake_on_motion());
+        let (_icm, _pin) = icm.release_int_pin();
+        pin.done();
+        i2c.done();
+    }
+
+    #[async_std::test]
+    async fn test_wake_on_motion_i2c() {
+        use crate::interrupt::InterruptPin;
+        use crate::motion::{WomCombine, WomMode};
+
+        let mut i2c = i2c::Mock::new(&[
+            // Accel in low power mode
+            i2c::Transaction::write_read(ADDR, vec![0x4E], vec![0x0F]),
+            i2c::Transaction::write(ADDR, vec![0x4E, 0x0E]),
+            i2c::Transaction::write_read(ADDR, vec![0x4D], vec![0x48]),
+            i2c::Transaction::write(ADDR, vec![0x4D, 0x40]),
+            // Thresholds in bank 4
+            i2c::Transaction::write(ADDR, vec![0x76, 4]),
+            i2c::Transaction::write(ADDR, vec![0x4A, 26]),
+            i2c::Transaction::write(ADDR, vec![0x4B, 26]),
+            i2c::Transaction::write(ADDR, vec![0x4C, 51]),
+            i2c::Transaction::write(ADDR, vec![0x76, 0]),
+            // INT_SOURCE1
+            i2c::Transaction::write_read(ADDR, vec![0x66], vec![0x00]),
+            i2c::Transaction::write(ADDR, vec![0x66, 0x07]),
+            // SMD_CONFIG
+            i2c::Transaction::write_read(ADDR, vec![0x57], vec![0x00]),
+            i2c::Transaction::write(ADDR, vec![0x57, 0x05]),
+            // INT_STATUS2
+            i2c::Transaction::write_read(ADDR, vec![0x37], vec![0x04]),
+        ]);
+        let mut config = crate::Config::default();
+        config.accel.odr = crate::config::AccelOdr::_100Hz;
+        let mut icm = ready(&mut i2c, &config);
+        icm.async_configure_wake_on_motion(
+            NoopDelay,
+            [100, 100, 200],
+            WomMode::Previous,
+            WomCombine::Or,
+            InterruptPin::Int1,
+        )
+        .await
+        .unwrap();
+        let axes = icm.async_read_wake_on_motion().await.unwrap();
+        assert!(axes.z && !axes.x && !axes.y);
+        i2c.done();
+    }
+
+    #[async_std::test]
+    async fn test_wake_on_motion_unsupported_odr() {
+        let mut i2c = i2c::Mock::new(&[]);
+        let mut icm = ready(&mut i2c, &Default::default());
+        let result = icm
+            .async_configure_wake_on_motion(
+                NoopDelay,
+                [100; 3],
+                Default::default(),
+                Default::default(),
+                crate::interrupt::InterruptPin::Int1,
+            )
+            .await;
+        assert_eq!(result, Err(super::Error::UnsupportedOdr));
+        i2c.done();
+    }
+
+    #[async_std::test]
+    async fn test_significant_motion_i2c() {
+        use crate::interrupt::InterruptPin;
+        use crate::motion::SmdWindow;
+
+        let mut i2c = i2c::Mock::new(&[
+            // INT_SOURCE1
+            i2c::Transaction::write_read(ADDR, vec![0x66], vec![0x07]),
+            i2c::Transaction::write(ADDR, vec![0x66, 0x0F]),
+            // SMD_CONFIG with WOM already enabled
+            i2c::Transaction::write_read(ADDR, vec![0x57], vec![0x05]),
+            i2c::Transaction::write(ADDR, vec![0x57, 0x07]),
+            // INT_STATUS2
+            i2c::Transaction::write_read(ADDR, vec![0x37], vec![0x09]),
+        ]);
+        let mut icm = ready(&mut i2c, &Default::default());
+        icm.async_enable_significant_motion(SmdWindow::Long, InterruptPin::Int1)
+            .await
+            .unwrap();
+        let event = icm.async_read_motion().await.unwrap();
+        assert!(event.significant_motion);
+        assert!(event.wake_on_motion.x);
+        i2c.done();
+    }
+
+    #[async_std::test]
+    async fn test_pedometer_i2c() {
+        use crate::apex::{ActivityClass, PedometerParams};
+        use crate::interrupt::InterruptPin;
+
+        let mut i2c = i2c::Mock::new(&[
+            // Accel in low power mode, DMP at 50 Hz
+            i2c::Transaction::write_read(ADDR, vec![0x4E], vec![0x0F]),
+            i2c::Transaction::write(ADDR, vec![0x4E, 0x0E]),
+            i2c::Transaction::write_read(ADDR, vec![0x4D], vec![0x48]),
+            i2c::Transaction::write(ADDR, vec![0x4D, 0x40]),
+            i2c::Transaction::write_read(ADDR, vec![0x56], vec![0x80]),
+            i2c::Transaction::write(ADDR, vec![0x56, 0x82]),
+            // APEX_CONFIG1-3 and 9 in bank 4
+            i2c::Transaction::write(ADDR, vec![0x76, 4]),
+            i2c::Transaction::write(ADDR, vec![0x40, 0xA2]),
+            i2c::Transaction::write(ADDR, vec![0x41, 0x85]),
+            i2c::Transaction::write(ADDR, vec![0x42, 0x51]),
+            i2c::Transaction::write(ADDR, vec![0x48, 0x00]),
+            i2c::Transaction::write(ADDR, vec![0x76, 0]),
+            // DMP memory reset and init
+            i2c::Transaction::write_read(ADDR, vec![0x4B], vec![0x00]),
+            i2c::Transaction::write(ADDR, vec![0x4B, 0x20]),
+            i2c::Transaction::write_read(ADDR, vec![0x4B], vec![0x00]),
+            i2c::Transaction::write(ADDR, vec![0x4B, 0x40]),
+            // INT_SOURCE6 in bank 4
+            i2c::Transaction::write(ADDR, vec![0x76, 4]),
+            i2c::Transaction::write_read(ADDR, vec![0x4D], vec![0x00]),
+            i2c::Transaction::write(ADDR, vec![0x4D, 0x30]),
+            i2c::Transaction::write(ADDR, vec![0x76, 0]),
+            // PED_ENABLE
+            i2c::Transaction::write_read(ADDR, vec![0x56], vec![0x82]),
+            i2c::Transaction::write(ADDR, vec![0x56, 0xA2]),
+            // APEX_DATA0-3
+            i2c::Transaction::write_read(ADDR, vec![0x31], vec![0x10, 0x00, 100, 0b10]),
+        ]);
+        let mut config = crate::Config::default();
+        config.accel.odr = crate::config::AccelOdr::_50Hz;
+        let mut icm = ready(&mut i2c, &config);
+        icm.async_enable_pedometer(NoopDelay, PedometerParams::default(), InterruptPin::Int1)
+            .await
+            .unwrap();
+        let pedometer = icm.async_read_pedometer().await.unwrap();
+        assert_eq!(pedometer.steps, 16);
+        assert_eq!(pedometer.cadence_hz, 2.0);
+        assert_eq!(pedometer.activity, ActivityClass::Run);
+        i2c.done();
+    }
+
+    #[async_std::test]
+    async fn test_pedometer_overflow_i2c() {
+        let mut i2c = i2c::Mock::new(&[
+            // STEP_CNT_OVF_INT in INT_STATUS3
+            i2c::Transaction::write_read(ADDR, vec![0x2D], vec![0x00]),
+            i2c::Transaction::write_read(ADDR, vec![0x37], vec![0x00, 0x10]),
+            // APEX_DATA0-3
+            i2c::Transaction::write_read(ADDR, vec![0x31], vec![0x05, 0x00, 0, 0]),
+        ]);
+        let mut icm = ready(&mut i2c, &Default::default());
+        let status = icm.async_read_interrupt_status().await.unwrap();
+        assert!(status.step_count_overflow());
+        let pedometer = icm.async_read_pedometer().await.unwrap();
+        assert_eq!(pedometer.steps, 65_541);
+        i2c.done();
+    }
+
+    #[async_std::test]
+    async fn test_pedometer_unsupported_odr() {
+        use crate::config::AccelOdr;
+
+        let mut i2c = i2c::Mock::new(&[]);
+        // Not available in low power mode, and slower than the DMP
+        for odr in [AccelOdr::_1kHz, AccelOdr::_25Hz] {
+            let mut config = crate::Config::default();
+            config.accel.odr = odr;
+            let mut icm = ready(&mut i2c, &config);
+            let result = icm
+                .async_enable_pedometer(
+                    NoopDelay,
+                    Default::default(),
+                    crate::interrupt::InterruptPin::Int1,
+                )
+                .await;
+            assert_eq!(result, Err(super::Error::UnsupportedOdr));
+        }
+        i2c.done();
+    }
+
+    #[async_std::test]
+    async fn test_tilt_i2c() {
+        use crate::apex::TiltWaitTime;
+        use crate::interrupt::{InterruptPin, Interrupts};
+
+        let mut i2c = i2c::Mock::new(&[
+            // Accel in low power mode, DMP at 25 Hz
+            i2c::Transaction::write_read(ADDR, vec![0x4E], vec![0x0F]),
+            i2c::Transaction::write(ADDR, vec![0x4E, 0x0E]),
+            i2c::Transaction::write_read(ADDR, vec![0x4D], vec![0x48]),
+            i2c::Transaction::write(ADDR, vec![0x4D, 0x40]),
+            i2c::Transaction::write_read(ADDR, vec![0x56], vec![0x82]),
+            i2c::Transaction::write(ADDR, vec![0x56, 0x80]),
+            // APEX_CONFIG4 in bank 4
+            i2c::Transaction::write(ADDR, vec![0x76, 4]),
+            i2c::Transaction::write_read(ADDR, vec![0x43], vec![0xA4]),
+            i2c::Transaction::write(ADDR, vec![0x43, 0x64]),
+            i2c::Transaction::write(ADDR, vec![0x76, 0]),
+            // DMP memory reset and init
+            i2c::Transaction::write_read(ADDR, vec![0x4B], vec![0x00]),
+            i2c::Transaction::write(ADDR, vec![0x4B, 0x20]),
+            i2c::Transaction::write_read(ADDR, vec![0x4B], vec![0x00]),
+            i2c::Transaction::write(ADDR, vec![0x4B, 0x40]),
+            // INT_SOURCE7 in bank 4
+            i2c::Transaction::write(ADDR, vec![0x76, 4]),
+            i2c::Transaction::write_read(ADDR, vec![0x4E], vec![0x00]),
+            i2c::Transaction::write(ADDR, vec![0x4E, 0x08]),
+            i2c::Transaction::write(ADDR, vec![0x76, 0]),
+            // TILT_ENABLE
+            i2c::Transaction::write_read(ADDR, vec![0x56], vec![0x80]),
+            i2c::Transaction::write(ADDR, vec![0x56, 0x90]),
+            // INT_STATUS, INT_STATUS2 and INT_STATUS3
+            i2c::Transaction::write_read(ADDR, vec![0x2D], vec![0x00]),
+            i2c::Transaction::write_read(ADDR, vec![0x37], vec![0x00, 0x08]),
+        ]);
+        let mut config = crate::Config::default();
+        config.accel.odr = crate::config::AccelOdr::_25Hz;
+        config.apex.dmp_odr = crate::config::DmpOdr::_25Hz;
+        let mut icm = ready(&mut i2c, &config);
+        icm.async_enable_tilt_detection(NoopDelay, TiltWaitTime::_2s, InterruptPin::Int2)
+            .await
+            .unwrap();
+        let status = icm.async_read_interrupt_status().await.unwrap();
+        assert_eq!(status, Interrupts::TILT);
+        assert!(status.tilt_event().is_some());
+        assert_eq!(status.wake_sleep_event(), None);
+        i2c.done();
+    }
+
+    #[async_std::test]
+    async fn test_tap_restarts_step_count_i2c() {
+        use crate::interrupt::InterruptPin;
+
+        let mut i2c = i2c::Mock::new(&[
+            // Pedometer
+            i2c::Transaction::write_read(ADDR, vec![0x4E], vec![0x0F]),
+            i2c::Transaction::write(ADDR, vec![0x4E, 0x0E]),
+            i2c::Transaction::write_read(ADDR, vec![0x4D], vec![0x48]),
+            i2c::Transaction::write(ADDR, vec![0x4D, 0x40]),
+            i2c::Transaction::write_read(ADDR, vec![0x56], vec![0x80]),
+            i2c::Transaction::write(ADDR, vec![0x56, 0x82]),
+            i2c::Transaction::write(ADDR, vec![0x76, 4]),
+            i2c::Transaction::write(ADDR, vec![0x40, 0xA2]),
+            i2c::Transaction::write(ADDR, vec![0x41, 0x85]),
+            i2c::Transaction::write(ADDR, vec![0x42, 0x51]),
+            i2c::Transaction::write(ADDR, vec![0x48, 0x00]),
+            i2c::Transaction::write(ADDR, vec![0x76, 0]),
+            i2c::Transaction::write_read(ADDR, vec![0x4B], vec![0x00]),
+            i2c::Transaction::write(ADDR, vec![0x4B, 0x20]),
+            i2c::Transaction::write_read(ADDR, vec![0x4B], vec![0x00]),
+            i2c::Transaction::write(ADDR, vec![0x4B, 0x40]),
+            i2c::Transaction::write(ADDR, vec![0x76, 4]),
+            i2c::Transaction::write_read(ADDR, vec![0x4D], vec![0x00]),
+            i2c::Transaction::write(ADDR, vec![0x4D, 0x30]),
+            i2c::Transaction::write(ADDR, vec![0x76, 0]),
+            i2c::Transaction::write_read(ADDR, vec![0x56], vec![0x82]),
+            i2c::Transaction::write(ADDR, vec![0x56, 0xA2]),
+            // 1000 steps
+            i2c::Transaction::write_read(ADDR, vec![0x31], vec![0xE8, 0x03, 0, 0]),
+            // Tap detection at 200 Hz, accel already in low power mode
+            i2c::Transaction::write_read(ADDR, vec![0x4E], vec![0x0E]),
+            i2c::Transaction::write(ADDR, vec![0x4E, 0x0E]),
+            i2c::Transaction::write_read(ADDR, vec![0x4D], vec![0x40]),
+            i2c::Transaction::write(ADDR, vec![0x4D, 0x40]),
+            i2c::Transaction::write_read(ADDR, vec![0x56], vec![0xA2]),
+            i2c::Transaction::write(ADDR, vec![0x56, 0xA2]),
+            i2c::Transaction::write_read(ADDR, vec![0x53], vec![0x0D]),
+            i2c::Transaction::write(ADDR, vec![0x53, 0x15]),
+            i2c::Transaction::write(ADDR, vec![0x76, 4]),
+            i2c::Transaction::write(ADDR, vec![0x46, 0x45]),
+            i2c::Transaction::write(ADDR, vec![0x47, 0x2B]),
+            i2c::Transaction::write(ADDR, vec![0x76, 0]),
+            // DMP memory reset clears the step count
+            i2c::Transaction::write_read(ADDR, vec![0x4B], vec![0x00]),
+            i2c::Transaction::write(ADDR, vec![0x4B, 0x20]),
+            i2c::Transaction::write_read(ADDR, vec![0x4B], vec![0x00]),
+            i2c::Transaction::write(ADDR, vec![0x4B, 0x40]),
+            i2c::Transaction::write(ADDR, vec![0x76, 4]),
+            i2c::Transaction::write_read(ADDR, vec![0x4D], vec![0x30]),
+            i2c::Transaction::write(ADDR, vec![0x4D, 0x31]),
+            i2c::Transaction::write(ADDR, vec![0x76, 0]),
+            i2c::Transaction::write_read(ADDR, vec![0x56], vec![0xA2]),
+            i2c::Transaction::write(ADDR, vec![0x56, 0xE2]),
+            // 3 steps since the reset
+            i2c::Transaction::write_read(ADDR, vec![0x31], vec![0x03, 0x00, 0, 0]),
+        ]);
+        let mut config = crate::Config::default();
+        config.accel.odr = crate::config::AccelOdr::_200Hz;
+        let mut icm = ready(&mut i2c, &config);
+        icm.async_enable_pedometer(NoopDelay, Default::default(), InterruptPin::Int1)
+            .await
+            .unwrap();
+        assert_eq!(icm.async_read_pedometer().await.unwrap().steps, 1000);
+        icm.async_enable_tap_detection(NoopDelay, Default::default(), InterruptPin::Int1)
+            .await
+            .unwrap();
+        assert_eq!(icm.async_read_pedometer().await.unwrap().steps, 3);
+        i2c.done();
+    }
+
+    #[async_std::test]
+    async fn test_tap_unsupported_odr() {
+        let mut i2c = i2c::Mock::new(&[]);
+        let mut config = crate::Config::default();
+        config.accel.odr = crate::config::AccelOdr::_100Hz;
+        let mut icm = ready(&mut i2c, &config);
+        let result = icm
+            .async_enable_tap_detection(
+                NoopDelay,
+                Default::default(),
+                crate::interrupt::InterruptPin::Int1,
+            )
+            .await;
+        assert_eq!(result, Err(super::Error::UnsupportedOdr));
+        i2c.done();
+    }
+}
+
+#[cfg(all(test, feature = "blocking"))]
+mod test_blocking {
+    use embedded_hal_mock::eh1::{delay::NoopDelay, i2c};
+    extern crate std;
+    use std::vec;
+
+    use super::test_util::{init_transactions, ready};
+    use crate::interface::{I2cInterface, I2C_ADDRESS_AD0_LOW as ADDR};
+    use crate::interrupt::{InterruptPin, Interrupts};
+
+    #[test]
+    fn test_init_i2c() {
+        let mut i2c = i2c::Mock::new(&init_transactions(&[]));
+        let icm = super::ICM42688::new(I2cInterface::new(&mut i2c, ADDR));
+        let _icm = icm.initialize(NoopDelay, Default::default()).unwrap();
+        i2c.done();
+    }
+
+    #[test]
+    fn test_read_fifo_into_i2c() {
+        // Two packets 4 in the FIFO, the buffer only fits one
+        let mut packet = vec![0x78];
+        packet.extend((1..20).map(|i| i as u8));
+        let mut i2c = i2c::Mock::new(&[
+            i2c::Transaction::write_read(ADDR, vec![0x2D], vec![0x04, 0x00, 0x28]),
+            i2c::Transaction::write_read(ADDR, vec![0x30], packet.clone()),
+        ]);
+        let mut icm = ready(&mut i2c, &Default::default());
+        let mut buffer = [0u8; 30];
+        let result = icm.read_fifo_into(&mut buffer).unwrap();
+        assert!(result.int_status.fifo_threshold());
+        assert_eq!(result.fifo_count, 40);
+        assert_eq!(result.bytes_read, 20);
+        assert_eq!(result.packets, 1);
+        assert!(result.data_left);
+        assert_eq!(&buffer[..20], &packet[..]);
+        i2c.done();
+    }
+
+    #[test]
+    fn test_interrupts_i2c() {
+        let mut i2c = i2c::Mock::new(&[
+            // INT_SOURCE0: FIFO threshold, INT_SOURCE6: step count overflow
+            i2c::Transaction::write_read(ADDR, vec![0x65], vec![0x10]),
+            i2c::Transaction::write(ADDR, vec![0x65, 0x14]),
+            i2c::Transaction::write(ADDR, vec![0x76, 4]),
+            i2c::Transaction::write_read(ADDR, vec![0x4D], vec![0x00]),
+            i2c::Transaction::write(ADDR, vec![0x4D, 0x10]),
+            i2c::Transaction::write(ADDR, vec![0x76, 0]),
+            // INT_SOURCE0 again, disabling the FIFO threshold
+            i2c::Transaction::write_read(ADDR, vec![0x65], vec![0x14]),
+            i2c::Transaction::write(ADDR, vec![0x65, 0x10]),
+            // INT_STATUS, INT_STATUS2 and INT_STATUS3
+            i2c::Transaction::write_read(ADDR, vec![0x2D], vec![0x04]),
+            i2c::Transaction::write_read(ADDR, vec![0x37], vec![0x00, 0x10]),
+        ]);
+        let mut icm = ready(&mut i2c, &Default::default());
+        icm.enable_interrupts(
+            InterruptPin::Int1,
+            Interrupts::FIFO_THRESHOLD | Interrupts::STEP_COUNT_OVERFLOW,
+        )
+        .unwrap();
+        icm.disable_interrupts(InterruptPin::Int1, Interrupts::FIFO_THRESHOLD)
+            .unwrap();
+        let status = icm.read_interrupt_status().unwrap();
+        assert_eq!(
+            status,
+            Interrupts::FIFO_THRESHOLD | Interrupts::STEP_COUNT_OVERFLOW
+        );
+        i2c.done();
+    }
+
+    #[test]
+    fn test_raise_to_wake_i2c() {
+        use crate::apex::{RaiseToWakeParams, WakeSleepEvent};
+
+        let mut i2c = i2c::Mock::new(&[
+            // Accel in low power mode, DMP at 50 Hz
+            i2c::Transaction::write_read(ADDR, vec![0x4E], vec![0x0F]),
+            i2c::Transaction::write(ADDR, vec![0x4E, 0x0E]),
+            i2c::Transaction::write_read(ADDR, vec![0x4D], vec![0x48]),
+            i2c::Transaction::write(ADDR, vec![0x4D, 0x40]),
+            i2c::Transaction::write_read(ADDR, vec![0x56], vec![0x80]),
+            i2c::Transaction::write(ADDR, vec![0x56, 0x82]),
+            // APEX_CONFIG4-6 in bank 4
+            i2c::Transaction::write(ADDR, vec![0x76, 4]),
+            i2c::Transaction::write_read(ADDR, vec![0x43], vec![0xA4]),
+            i2c::Transaction::write(ADDR, vec![0x43, 0xA4]),
+            i2c::Transaction::write_read(ADDR, vec![0x44], vec![0x00]),
+            i2c::Transaction::write(ADDR, vec![0x44, 0x00]),
+            i2c::Transaction::write_read(ADDR, vec![0x45], vec![0x04]),
+            i2c::Transaction::write(ADDR, vec![0x45, 0x04]),
+            i2c::Transaction::write(ADDR, vec![0x76, 0]),
+            // DMP memory reset and init
+            i2c::Transaction::write_read(ADDR, vec![0x4B], vec![0x00]),
+            i2c::Transaction::write(ADDR, vec![0x4B, 0x20]),
+            i2c::Transaction::write_read(ADDR, vec![0x4B], vec![0x00]),
+            i2c::Transaction::write(ADDR, vec![0x4B, 0x40]),
+            // INT_SOURCE6 in bank 4
+            i2c::Transaction::write(ADDR, vec![0x76, 4]),
+            i2c::Transaction::write_read(ADDR, vec![0x4D], vec![0x00]),
+            i2c::Transaction::write(ADDR, vec![0x4D, 0x06]),
+            i2c::Transaction::write(ADDR, vec![0x76, 0]),
+            // R2W_EN
+            i2c::Transaction::write_read(ADDR, vec![0x56], vec![0x82]),
+            i2c::Transaction::write(ADDR, vec![0x56, 0x8A]),
+            // INT_STATUS, INT_STATUS2 and INT_STATUS3
+            i2c::Transaction::write_read(ADDR, vec![0x2D], vec![0x00]),
+            i2c::Transaction::write_read(ADDR, vec![0x37], vec![0x00, 0x04]),
+        ]);
+        let mut config = crate::Config::default();
+        config.accel.odr = crate::config::AccelOdr::_50Hz;
+        let mut icm = ready(&mut i2c, &config);
+        icm.enable_raise_to_wake(NoopDelay, RaiseToWakeParams::default(), InterruptPin::Int1)
+            .unwrap();
+        let status = icm.read_interrupt_status().unwrap();
+        assert_eq!(status.wake_sleep_event(), Some(WakeSleepEvent::Wake));
+        i2c.done();
+    }
 }
@@ -1,5 +1,10 @@
 use crate::register_bank::{RegisterBank, Registers, BANK0};
 
+/// Low level driver
+///
+/// `BUS` can be anything that implements
+/// [`RegisterInterface`](crate::RegisterInterface), register access is only
+/// available for such buses.
 pub struct ICM42688<BUS> {
     pub(crate) bus: BUS,
     current_bank: RegisterBank,
@@ -37,13 +42,26 @@ impl<BUS> ICM42688<BUS> {
         self.current_bank
     }
 
-    pub fn bank<const BANK: RegisterBank>(&mut self) -> Registers<BUS, BANK> {
+    pub fn bank<const BANK: RegisterBank>(&mut self) -> Registers<'_, BUS, BANK> {
         if self.current_bank != BANK {
             panic!("Bank mismatch")
         }
         Registers::new(&mut self.bus)
     }
 
+    /// Access the registers of `BANK`
+    ///
+    /// Returns an error instead of panicking if `BANK` is not the currently
+    /// selected bank.
+    pub fn try_bank<const BANK: RegisterBank>(
+        &mut self,
+    ) -> Result<Registers<'_, BUS, BANK>, BankSelectionError> {
+        if self.current_bank != BANK {
+            return Err(BankSelectionError);
+        }
+        Ok(Registers::new(&mut self.bus))
+    }
+
     /// Get a reference to the bus
     pub fn bus(&mut self) -> &mut BUS {
         &mut self.bus
@@ -66,6 +84,47 @@ mod test {
     use embedded_hal_mock::eh1::digital::{State as PinState, Transaction as PinTransaction};
     use embedded_hal_mock::eh1::spi::{Mock as SpiMock, Transaction as SpiTransaction};
 
+    /// Register file standing in for the device
+    struct FakeRegisters {
+        regs: [u8; 128],
+    }
+
+    impl crate::interface::Interface for FakeRegisters {
+        type Error = core::convert::Infallible;
+
+        const KIND: crate::interface::InterfaceKind = crate::interface::InterfaceKind::Spi;
+    }
+
+    #[cfg(feature = "blocking")]
+    impl crate::RegisterInterface for FakeRegisters {
+        fn read_regs(&mut self, addr: u8, buf: &mut [u8]) -> Result<(), Self::Error> {
+            let addr = addr as usize;
+            buf.copy_from_slice(&self.regs[addr..addr + buf.len()]);
+            Ok(())
+        }
+
+        fn write_regs(&mut self, addr: u8, data: &[u8]) -> Result<(), Self::Error> {
+            let addr = addr as usize;
+            self.regs[addr..addr + data.len()].copy_from_slice(data);
+            Ok(())
+        }
+    }
+
+    #[cfg(feature = "async")]
+    impl crate::AsyncRegisterInterface for FakeRegisters {
+        async fn read_regs(&mut self, addr: u8, buf: &mut [u8]) -> Result<(), Self::Error> {
+            let addr = addr as usize;
+            buf.copy_from_slice(&self.regs[addr..addr + buf.len()]);
+            Ok(())
+        }
+
+        async fn write_regs(&mut self, addr: u8, data: &[u8]) -> Result<(), Self::Error> {
+            let addr = addr as usize;
+            self.regs[addr..addr + data.len()].copy_from_slice(data);
+            Ok(())
+        }
+    }
+
     #[test]
     fn test_bank_noop() {
         let expectations: &[SpiTransaction<u8>] = &[];
@@ -99,7 +158,7 @@ mod test {
         pin.done();
     }
 
-    #[cfg(not(feature = "async"))]
+    #[cfg(feature = "blocking")]
     #[test]
     fn test_who_am_i() {
         let expectations: &[SpiTransaction<u8>] = &[
@@ -125,6 +184,63 @@ mod test {
         pin.done();
     }
 
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn test_who_am_i_i2c() {
+        use crate::interface::{I2cInterface, I2C_ADDRESS_AD0_LOW};
+        use embedded_hal_mock::eh1::i2c::{Mock as I2cMock, Transaction as I2cTransaction};
+
+        let expectations = [I2cTransaction::write_read(
+            I2C_ADDRESS_AD0_LOW,
+            vec![0x75],
+            vec![0x47],
+        )];
+
+        let i2c = I2cMock::new(&expectations);
+        let mut icm = ICM42688::new(I2cInterface::new(i2c, I2C_ADDRESS_AD0_LOW));
+        let mut bank = icm.bank::<BANK0>();
+        let whoami = bank.who_am_i().read().unwrap().value();
+        assert_eq!(whoami, 0x47);
+
+        let mut i2c = icm.release().release();
+        i2c.done();
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn test_modify_fake() {
+        let mut regs = [0; 128];
+        regs[0x14] = 0b1100_0000;
+        let mut icm = ICM42688::new(FakeRegisters { regs });
+        let mut bank = icm.bank::<BANK0>();
+        bank.int_config()
+            .modify(|_, w| w.int1_mode(1).int1_polarity(1))
+            .unwrap();
+        assert_eq!(bank.int_config().read().unwrap().int1_mode(), 1);
+
+        let fake = icm.release();
+        assert_eq!(fake.regs[0x14], 0b1100_0101);
+    }
+
+    #[cfg(feature = "async")]
+    #[async_std::test]
+    async fn test_mutations_fake() {
+        let mut regs = [0; 128];
+        regs[0x14] = 0b1100_0000;
+        let mut icm = ICM42688::new(FakeRegisters { regs });
+        let mut bank = icm.bank::<BANK0>();
+        let mutations = [
+            bank.int_config()
+                .mutation(|w| w.int1_mode(1).int1_polarity(1)),
+            bank.fifo_config().mutation(|w| w.fifo_mode(0b01)),
+        ];
+        bank.async_apply_mutations(&mutations).await.unwrap();
+
+        let fake = icm.release();
+        assert_eq!(fake.regs[0x14], 0b1100_0101);
+        assert_eq!(fake.regs[0x16], 0b0100_0000);
+    }
+
     #[cfg(feature = "async")]
     #[async_std::test]
     async fn test_who_am_i_async() {
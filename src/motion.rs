@@ -0,0 +1,327 @@
+//! Wake on motion and significant motion detection
+//!
+//! The accelerometer is put into low power mode and compares every sample
+//! against per-axis thresholds. A sample exceeding them raises the WOM
+//! interrupts, see [`Interrupts::WOM`].
+//!
+//! Significant motion detection builds on top of that: it fires when two WOM
+//! events occur a given time apart, which filters out single bumps.
+
+use crate::{
+    interrupt::{InterruptPin, Interrupts},
+    register_bank::{bank0::INT_STATUS2, Register, BANK0, BANK4},
+    Error, Ready, ICM42688,
+};
+
+#[cfg(feature = "async")]
+use crate::interface::AsyncRegisterInterface;
+
+#[cfg(feature = "blocking")]
+use crate::interface::RegisterInterface;
+
+/// `SMD_MODE` value that enables wake on motion only
+const SMD_MODE_WOM: u8 = 0b01;
+
+/// Address of `INT_STATUS2`
+const INT_STATUS2_ADDR: u8 = INT_STATUS2::ID;
+
+/// Time between the two WOM events of a significant motion, `SMD_MODE` in
+/// `SMD_CONFIG`
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum SmdWindow {
+    /// 1 s
+    #[default]
+    Short = 0b10,
+    /// 3 s
+    Long = 0b11,
+}
+
+/// What samples are compared against, `WOM_MODE` in `SMD_CONFIG`
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum WomMode {
+    /// The first sample after enabling wake on motion
+    Initial = 0,
+    /// The previous sample
+    #[default]
+    Previous = 1,
+}
+
+/// How the axes are combined, `WOM_INT_MODE` in `SMD_CONFIG`
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum WomCombine {
+    /// Any axis exceeds its threshold
+    #[default]
+    Or = 0,
+    /// All axes exceed their thresholds
+    And = 1,
+}
+
+/// Axes that exceeded their wake on motion threshold
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq, defmt::Format)]
+pub struct WomAxes {
+    pub x: bool,
+    pub y: bool,
+    pub z: bool,
+}
+
+impl WomAxes {
+    pub fn any(self) -> bool {
+        self.x || self.y || self.z
+    }
+}
+
+/// Decoded `INT_STATUS2` flags
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq, defmt::Format)]
+pub struct MotionEvent {
+    pub wake_on_motion: WomAxes,
+    /// Two WOM events occurred the [`SmdWindow`] apart
+    pub significant_motion: bool,
+}
+
+impl Interrupts {
+    /// The axes that raised a wake on motion interrupt
+    pub fn wom_axes(self) -> WomAxes {
+        WomAxes {
+            x: self.intersects(Self::WOM_X),
+            y: self.intersects(Self::WOM_Y),
+            z: self.intersects(Self::WOM_Z),
+        }
+    }
+
+    /// The wake on motion and significant motion flags
+    pub fn motion(self) -> MotionEvent {
+        MotionEvent {
+            wake_on_motion: self.wom_axes(),
+            significant_motion: self.significant_motion(),
+        }
+    }
+}
+
+/// Convert a threshold in mg to the register value, 1 LSB is 1/256 g
+fn wom_threshold(mg: u16) -> u8 {
+    let lsb = (mg as u32 * 256 + 500) / 1000;
+    lsb.min(u8::MAX as u32) as u8
+}
+
+impl<BUS, PIN> ICM42688<BUS, Ready<PIN>> {
+    /// Arm wake on motion and route it to `pin`
+    ///
+    /// Switches the accelerometer to low power mode, the gyro mode is not
+    /// changed. Returns [`Error::UnsupportedOdr`] if the accel ODR is not
+    /// supported in low power mode (above 500 Hz). Thresholds are in mg, in
+    /// the range 0 to 1000 with a resolution of ~3.9 mg.
+    #[cfg(feature = "async")]
+    pub async fn async_configure_wake_on_motion(
+        &mut self,
+        mut delay: impl embedded_hal_async::delay::DelayNs,
+        thresholds_mg: [u16; 3],
+        mode: WomMode,
+        and_or: WomCombine,
+        pin: InterruptPin,
+    ) -> Result<(), Error<BUS::Error>>
+    where
+        BUS: AsyncRegisterInterface,
+    {
+        // Refer to Section 8.7 of the datasheet
+        self.async_accel_low_power().await?;
+        delay.delay_ms(1).await;
+
+        let [x, y, z] = thresholds_mg.map(wom_threshold);
+        self.async_select_bank(BANK4).await?;
+        let mut bank4 = self.ll.try_bank::<4>()?;
+        bank4
+            .accel_wom_x_thr()
+            .async_write(|w| w.wom_x_th(x))
+            .await?;
+        bank4
+            .accel_wom_y_thr()
+            .async_write(|w| w.wom_y_th(y))
+            .await?;
+        bank4
+            .accel_wom_z_thr()
+            .async_write(|w| w.wom_z_th(z))
+            .await?;
+        self.async_select_bank(BANK0).await?;
+        delay.delay_ms(1).await;
+
+        self.async_enable_interrupts(pin, Interrupts::WOM).await?;
+        delay.delay_ms(50).await;
+
+        self.ll
+            .try_bank::<0>()?
+            .smd_config()
+            .async_modify(|w| {
+                w.wom_int_mode(and_or as u8)
+                    .wom_mode(mode as u8)
+                    .smd_mode(SMD_MODE_WOM)
+            })
+            .await?;
+        Ok(())
+    }
+
+    /// Arm wake on motion and route it to `pin`
+    ///
+    /// Switches the accelerometer to low power mode, the gyro mode is not
+    /// changed. Returns [`Error::UnsupportedOdr`] if the accel ODR is not
+    /// supported in low power mode (above 500 Hz). Thresholds are in mg, in
+    /// the range 0 to 1000 with a resolution of ~3.9 mg.
+    #[cfg(feature = "blocking")]
+    pub fn configure_wake_on_motion(
+        &mut self,
+        mut delay: impl embedded_hal::delay::DelayNs,
+        thresholds_mg: [u16; 3],
+        mode: WomMode,
+        and_or: WomCombine,
+        pin: InterruptPin,
+    ) -> Result<(), Error<BUS::Error>>
+    where
+        BUS: RegisterInterface,
+    {
+        // Refer to Section 8.7 of the datasheet
+        self.accel_low_power()?;
+        delay.delay_ms(1);
+
+        let [x, y, z] = thresholds_mg.map(wom_threshold);
+        self.select_bank(BANK4)?;
+        let mut bank4 = self.ll.try_bank::<4>()?;
+        bank4.accel_wom_x_thr().write(|w| w.wom_x_th(x))?;
+        bank4.accel_wom_y_thr().write(|w| w.wom_y_th(y))?;
+        bank4.accel_wom_z_thr().write(|w| w.wom_z_th(z))?;
+        self.select_bank(BANK0)?;
+        delay.delay_ms(1);
+
+        self.enable_interrupts(pin, Interrupts::WOM)?;
+        delay.delay_ms(50);
+
+        self.ll.try_bank::<0>()?.smd_config().modify(|_, w| {
+            w.wom_int_mode(and_or as u8)
+                .wom_mode(mode as u8)
+                .smd_mode(SMD_MODE_WOM)
+        })?;
+        Ok(())
+    }
+
+    /// Enable significant motion detection and route it to `pin`
+    ///
+    /// Wake on motion has to be configured first with
+    /// [`configure_wake_on_motion`](ICM42688::configure_wake_on_motion), its
+    /// thresholds are used to detect motion. The WOM interrupts stay routed,
+    /// disable them with [`disable_interrupts`](ICM42688::disable_interrupts)
+    /// to only wake up on significant motion.
+    #[cfg(feature = "async")]
+    pub async fn async_enable_significant_motion(
+        &mut self,
+        window: SmdWindow,
+        pin: InterruptPin,
+    ) -> Result<(), Error<BUS::Error>>
+    where
+        BUS: AsyncRegisterInterface,
+    {
+        self.async_enable_interrupts(pin, Interrupts::SMD).await?;
+        self.ll
+            .try_bank::<0>()?
+            .smd_config()
+            .async_modify(|w| w.smd_mode(window as u8))
+            .await?;
+        Ok(())
+    }
+
+    /// Enable significant motion detection and route it to `pin`
+    ///
+    /// Wake on motion has to be configured first with
+    /// [`configure_wake_on_motion`](ICM42688::configure_wake_on_motion), its
+    /// thresholds are used to detect motion. The WOM interrupts stay routed,
+    /// disable them with [`disable_interrupts`](ICM42688::disable_interrupts)
+    /// to only wake up on significant motion.
+    #[cfg(feature = "blocking")]
+    pub fn enable_significant_motion(
+        &mut self,
+        window: SmdWindow,
+        pin: InterruptPin,
+    ) -> Result<(), Error<BUS::Error>>
+    where
+        BUS: RegisterInterface,
+    {
+        self.enable_interrupts(pin, Interrupts::SMD)?;
+        self.ll
+            .try_bank::<0>()?
+            .smd_config()
+            .modify(|_, w| w.smd_mode(window as u8))?;
+        Ok(())
+    }
+
+    /// Read and clear the wake on motion and significant motion flags
+    ///
+    /// Only reads `INT_STATUS2`, the other status flags are left untouched.
+    #[cfg(feature = "async")]
+    pub async fn async_read_motion(&mut self) -> Result<MotionEvent, Error<BUS::Error>>
+    where
+        BUS: AsyncRegisterInterface,
+    {
+        self.ll.try_bank::<0>()?;
+        let mut status2 = [0];
+        self.ll
+            .bus
+            .read_regs(INT_STATUS2_ADDR, &mut status2)
+            .await
+            .map_err(Error::Bus)?;
+        Ok(Interrupts::from_registers([0, status2[0], 0]).motion())
+    }
+
+    /// Read and clear the wake on motion and significant motion flags
+    ///
+    /// Only reads `INT_STATUS2`, the other status flags are left untouched.
+    #[cfg(feature = "blocking")]
+    pub fn read_motion(&mut self) -> Result<MotionEvent, Error<BUS::Error>>
+    where
+        BUS: RegisterInterface,
+    {
+        self.ll.try_bank::<0>()?;
+        let mut status2 = [0];
+        self.ll
+            .bus
+            .read_regs(INT_STATUS2_ADDR, &mut status2)
+            .map_err(Error::Bus)?;
+        Ok(Interrupts::from_registers([0, status2[0], 0]).motion())
+    }
+
+    /// Read and clear the wake on motion flags
+    ///
+    /// Clears the significant motion flag as well, use
+    /// [`read_motion`](ICM42688::read_motion) to get both.
+    #[cfg(feature = "async")]
+    pub async fn async_read_wake_on_motion(&mut self) -> Result<WomAxes, Error<BUS::Error>>
+    where
+        BUS: AsyncRegisterInterface,
+    {
+        Ok(self.async_read_motion().await?.wake_on_motion)
+    }
+
+    /// Read and clear the wake on motion flags
+    ///
+    /// Clears the significant motion flag as well, use
+    /// [`read_motion`](ICM42688::read_motion) to get both.
+    #[cfg(feature = "blocking")]
+    pub fn read_wake_on_motion(&mut self) -> Result<WomAxes, Error<BUS::Error>>
+    where
+        BUS: RegisterInterface,
+    {
+        Ok(self.read_motion()?.wake_on_motion)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_wom_threshold() {
+        assert_eq!(wom_threshold(0), 0);
+        assert_eq!(wom_threshold(100), 26);
+        assert_eq!(wom_threshold(1000), 0xFF);
+        assert_eq!(wom_threshold(u16::MAX), 0xFF);
+    }
+}
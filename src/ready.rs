@@ -1,117 +1,463 @@
+use crate::{
+    config::{AccelFullScale, AccelMode, DataMode, Fifo, GyroFullScale},
+    fifo::{FifoParser, FifoReadResult},
+    interrupt::InterruptStatus,
+    register_bank::{Register, RegisterBank},
+    sample::FullScale,
+    timestamp::TimestampTracker,
+    Error, Ready, ICM42688,
+};
+
 #[cfg(feature = "async")]
-use embedded_hal_async::spi::SpiDevice;
+use crate::interface::AsyncRegisterInterface;
+
+#[cfg(feature = "blocking")]
+use crate::interface::RegisterInterface;
+
+/// We read INT_STATUS, FIFO_COUNT_H, FIFO_COUNT_L, and then the data in one go
+const INT_STATUS_ADDR: u8 = crate::register_bank::bank0::INT_STATUS::ID;
 
-#[cfg(not(feature = "async"))]
-use embedded_hal::spi::SpiDevice;
+/// Padding byte, INT_STATUS, FIFO_COUNT_H and FIFO_COUNT_L
+const FIFO_HEADER_LEN: usize = 4;
 
-use crate::{register_bank::Register, Ready, ICM42688};
+const FIFO_DATA_ADDR: u8 = crate::register_bank::bank0::FIFO_DATA::ID;
 
-#[derive(Debug, defmt::Format, Copy, Clone)]
-pub struct FifoReadError;
+/// REG_BANK_SEL is at the same address in every bank
+const REG_BANK_SEL_ADDR: u8 = crate::register_bank::bank0::REG_BANK_SEL::ID;
 
-impl<SPI> ICM42688<SPI, Ready>
-where
-    SPI: SpiDevice,
-{
+impl<BUS, PIN> ICM42688<BUS, Ready<PIN>> {
     #[cfg(feature = "async")]
-    pub async fn reset_fifo(&mut self) {
-        let mut bank0 = self.ll.bank::<0>();
+    pub async fn async_reset_fifo(&mut self) -> Result<(), Error<BUS::Error>>
+    where
+        BUS: AsyncRegisterInterface,
+    {
+        let mut bank0 = self.ll.try_bank::<0>()?;
         bank0
             .signal_path_reset()
             .async_modify(|w| w.fifo_flush(1))
-            .await
-            .unwrap();
+            .await?;
+        Ok(())
     }
 
-    #[cfg(not(feature = "async"))]
-    pub fn reset_fifo(&mut self) {
-        let mut bank0 = self.ll.bank::<0>();
-        bank0
-            .signal_path_reset()
-            .modify(|_, w| w.fifo_flush(1))
-            .unwrap();
+    #[cfg(feature = "blocking")]
+    pub fn reset_fifo(&mut self) -> Result<(), Error<BUS::Error>>
+    where
+        BUS: RegisterInterface,
+    {
+        let mut bank0 = self.ll.try_bank::<0>()?;
+        bank0.signal_path_reset().modify(|_, w| w.fifo_flush(1))?;
+        Ok(())
     }
 
+    /// Read the FIFO count
+    ///
+    /// The count is in bytes, or in records if
+    /// [`count_records`](crate::config::Fifo::count_records) is set. Use
+    /// [`Fifo::count_to_bytes`] to convert it.
     #[cfg(feature = "async")]
-    pub async fn read_fifo_count(&mut self) -> u16 {
-        let mut bank0 = self.ll.bank::<0>();
-        let count_h = bank0
-            .fifo_counth()
-            .async_read()
-            .await
-            .unwrap()
-            .fifo_count_15_8();
-        let count_l = bank0
-            .fifo_countl()
-            .async_read()
-            .await
-            .unwrap()
-            .fifo_count_7_0();
-        ((count_h as u16) << 8) | count_l as u16
+    pub async fn async_read_fifo_count(&mut self) -> Result<u16, Error<BUS::Error>>
+    where
+        BUS: AsyncRegisterInterface,
+    {
+        let mut bank0 = self.ll.try_bank::<0>()?;
+        let count_h = bank0.fifo_counth().async_read().await?.fifo_count_15_8();
+        let count_l = bank0.fifo_countl().async_read().await?.fifo_count_7_0();
+        Ok(((count_h as u16) << 8) | count_l as u16)
     }
 
-    #[cfg(not(feature = "async"))]
-    pub async fn read_fifo_count(&mut self) -> u16 {
-        let mut bank0 = self.ll.bank::<0>();
-        let count_h = bank0.fifo_counth().read().unwrap().fifo_count_15_8();
-        let count_l = bank0.fifo_countl().read().unwrap().fifo_count_7_0();
-        ((count_h as u16) << 8) | count_l as u16
+    /// Read the FIFO count
+    ///
+    /// The count is in bytes, or in records if
+    /// [`count_records`](crate::config::Fifo::count_records) is set. Use
+    /// [`Fifo::count_to_bytes`] to convert it.
+    #[cfg(feature = "blocking")]
+    pub fn read_fifo_count(&mut self) -> Result<u16, Error<BUS::Error>>
+    where
+        BUS: RegisterInterface,
+    {
+        let mut bank0 = self.ll.try_bank::<0>()?;
+        let count_h = bank0.fifo_counth().read()?.fifo_count_15_8();
+        let count_l = bank0.fifo_countl().read()?.fifo_count_7_0();
+        Ok(((count_h as u16) << 8) | count_l as u16)
     }
 
     /// Read data from the FIFO
     ///
-    /// NOTE: Only the 4-byte packet mode is supported
+    /// Returns the number of packets that were in the FIFO, the packet size
+    /// follows from [`config::Fifo`](crate::config::Fifo).
     ///
-    /// Buffer must hold at least
+    /// Buffer must hold at least one word for the padding byte, INT_STATUS and
+    /// the FIFO count, otherwise [`Error::BufferTooSmall`] is returned.
     #[cfg(feature = "async")]
-    pub async fn read_fifo(&mut self, buffer: &mut [u32]) -> Result<usize, FifoReadError> {
-        /// We read INT_STATUS, FIFO_COUNT_H, FIFO_COUNT_L, and then the data in one go
-        const INT_STATUS_ADDR: u8 = crate::register_bank::bank0::INT_STATUS::ID;
-
+    pub async fn async_read_fifo(&mut self, buffer: &mut [u32]) -> Result<usize, Error<BUS::Error>>
+    where
+        BUS: AsyncRegisterInterface,
+    {
+        self.check_fifo_data_mode()?;
         let buffer = bytemuck::cast_slice_mut::<u32, u8>(buffer);
-
-        buffer[0] = INT_STATUS_ADDR | 0x80; // Read bit set
+        if buffer.len() < FIFO_HEADER_LEN {
+            return Err(Error::BufferTooSmall);
+        }
 
         self.ll
             .bus
-            .transfer_in_place(buffer)
+            .read_regs(INT_STATUS_ADDR, &mut buffer[1..])
             .await
-            .map_err(|_| FifoReadError)?;
+            .map_err(Error::Bus)?;
 
-        // Buffer now contains [0, INT_STATUS, FIFO_COUNT_H, FIFO_COUNT_L, DATA, DATA, ...]
+        // Buffer now contains [_, INT_STATUS, FIFO_COUNT_H, FIFO_COUNT_L, DATA, DATA, ...]
         // We need to check the FIFO_COUNT and then return the number of samples read
         let fifo_count = ((buffer[2] as u16) << 8) | (buffer[3] as u16);
 
-        Ok(fifo_count as usize / 20)
+        Ok(self.state.inner.fifo.count_to_packets(fifo_count))
     }
 
-    #[cfg(not(feature = "async"))]
-    pub fn read_fifo(&mut self, buffer: &mut [u32]) -> Result<usize, FifoReadError> {
-        /// We read INT_STATUS, FIFO_COUNT_H, FIFO_COUNT_L, and then the data in one go
-        const INT_STATUS_ADDR: u8 = crate::register_bank::bank0::INT_STATUS::ID;
-
+    /// Read data from the FIFO
+    ///
+    /// Returns the number of packets that were in the FIFO, the packet size
+    /// follows from [`config::Fifo`](crate::config::Fifo).
+    ///
+    /// Buffer must hold at least one word for the padding byte, INT_STATUS and
+    /// the FIFO count, otherwise [`Error::BufferTooSmall`] is returned.
+    #[cfg(feature = "blocking")]
+    pub fn read_fifo(&mut self, buffer: &mut [u32]) -> Result<usize, Error<BUS::Error>>
+    where
+        BUS: RegisterInterface,
+    {
+        self.check_fifo_data_mode()?;
         let buffer = bytemuck::cast_slice_mut::<u32, u8>(buffer);
-
-        buffer[0] = INT_STATUS_ADDR | 0x80; // Read bit set
+        if buffer.len() < FIFO_HEADER_LEN {
+            return Err(Error::BufferTooSmall);
+        }
 
         self.ll
             .bus
-            .transfer_in_place(buffer)
-            .map_err(|_| FifoReadError)?;
+            .read_regs(INT_STATUS_ADDR, &mut buffer[1..])
+            .map_err(Error::Bus)?;
 
-        // Buffer now contains [0, INT_STATUS, FIFO_COUNT_H, FIFO_COUNT_L, DATA, DATA, ...]
+        // Buffer now contains [_, INT_STATUS, FIFO_COUNT_H, FIFO_COUNT_L, DATA, DATA, ...]
         // We need to check the FIFO_COUNT and then return the number of samples read
         let fifo_count = ((buffer[2] as u16) << 8) | (buffer[3] as u16);
 
-        Ok(fifo_count as usize / 20)
+        Ok(self.state.inner.fifo.count_to_packets(fifo_count))
+    }
+
+    /// Read data from the FIFO into a byte buffer
+    ///
+    /// Reads INT_STATUS and the FIFO count first and then only as many whole
+    /// packets as fit into `buffer`. The data is written to the start of
+    /// `buffer`, which can have any alignment, and can be decoded with a
+    /// [`FifoParser`]. Returns [`Error::BufferTooSmall`] if `buffer` can't
+    /// hold a single packet.
+    #[cfg(feature = "async")]
+    pub async fn async_read_fifo_into(
+        &mut self,
+        buffer: &mut [u8],
+    ) -> Result<FifoReadResult, Error<BUS::Error>>
+    where
+        BUS: AsyncRegisterInterface,
+    {
+        self.check_fifo_data_mode()?;
+        let mut status = [0; 3];
+        self.ll
+            .bus
+            .read_regs(INT_STATUS_ADDR, &mut status)
+            .await
+            .map_err(Error::Bus)?;
+        let fifo_count = self
+            .state
+            .inner
+            .fifo
+            .count_to_bytes(u16::from_be_bytes([status[1], status[2]]));
+        let len = self.fifo_read_len(fifo_count, buffer.len())?;
+
+        if len > 0 {
+            self.ll
+                .bus
+                .read_regs(FIFO_DATA_ADDR, &mut buffer[..len])
+                .await
+                .map_err(Error::Bus)?;
+        }
+
+        Ok(Self::fifo_read_result(
+            status[0],
+            fifo_count,
+            &buffer[..len],
+        ))
+    }
+
+    /// Read data from the FIFO into a byte buffer
+    ///
+    /// Reads INT_STATUS and the FIFO count first and then only as many whole
+    /// packets as fit into `buffer`. The data is written to the start of
+    /// `buffer`, which can have any alignment, and can be decoded with a
+    /// [`FifoParser`]. Returns [`Error::BufferTooSmall`] if `buffer` can't
+    /// hold a single packet.
+    #[cfg(feature = "blocking")]
+    pub fn read_fifo_into(&mut self, buffer: &mut [u8]) -> Result<FifoReadResult, Error<BUS::Error>>
+    where
+        BUS: RegisterInterface,
+    {
+        self.check_fifo_data_mode()?;
+        let mut status = [0; 3];
+        self.ll
+            .bus
+            .read_regs(INT_STATUS_ADDR, &mut status)
+            .map_err(Error::Bus)?;
+        let fifo_count = self
+            .state
+            .inner
+            .fifo
+            .count_to_bytes(u16::from_be_bytes([status[1], status[2]]));
+        let len = self.fifo_read_len(fifo_count, buffer.len())?;
+
+        if len > 0 {
+            self.ll
+                .bus
+                .read_regs(FIFO_DATA_ADDR, &mut buffer[..len])
+                .map_err(Error::Bus)?;
+        }
+
+        Ok(Self::fifo_read_result(
+            status[0],
+            fifo_count,
+            &buffer[..len],
+        ))
+    }
+
+    /// Drain the FIFO in chunks
+    ///
+    /// Reads the FIFO count once and then reads everything that was in the
+    /// FIFO in bursts of at most `buffer.len()` bytes, calling `f` with every
+    /// chunk. Chunks always hold whole packets, so they can be decoded with a
+    /// [`FifoParser`] on their own. Returns the number of bytes drained.
+    ///
+    /// Packets written to the FIFO after the count was read are left for the
+    /// next call. Returns [`Error::BufferTooSmall`] if `buffer` can't hold a
+    /// single packet.
+    #[cfg(feature = "async")]
+    pub async fn async_drain_fifo(
+        &mut self,
+        buffer: &mut [u8],
+        mut f: impl FnMut(&[u8]),
+    ) -> Result<usize, Error<BUS::Error>>
+    where
+        BUS: AsyncRegisterInterface,
+    {
+        self.check_fifo_data_mode()?;
+        let count = self.async_read_fifo_count().await?;
+        let mut remaining = self.state.inner.fifo.count_to_bytes(count);
+        let mut drained = 0;
+
+        loop {
+            let len = self.fifo_read_len(remaining, buffer.len())?;
+            if len == 0 {
+                break;
+            }
+            self.ll
+                .bus
+                .read_regs(FIFO_DATA_ADDR, &mut buffer[..len])
+                .await
+                .map_err(Error::Bus)?;
+            f(&buffer[..len]);
+            remaining -= len;
+            drained += len;
+        }
+
+        Ok(drained)
+    }
+
+    /// Drain the FIFO in chunks
+    ///
+    /// Reads the FIFO count once and then reads everything that was in the
+    /// FIFO in bursts of at most `buffer.len()` bytes, calling `f` with every
+    /// chunk. Chunks always hold whole packets, so they can be decoded with a
+    /// [`FifoParser`] on their own. Returns the number of bytes drained.
+    ///
+    /// Packets written to the FIFO after the count was read are left for the
+    /// next call. Returns [`Error::BufferTooSmall`] if `buffer` can't hold a
+    /// single packet.
+    #[cfg(feature = "blocking")]
+    pub fn drain_fifo(
+        &mut self,
+        buffer: &mut [u8],
+        mut f: impl FnMut(&[u8]),
+    ) -> Result<usize, Error<BUS::Error>>
+    where
+        BUS: RegisterInterface,
+    {
+        self.check_fifo_data_mode()?;
+        let count = self.read_fifo_count()?;
+        let mut remaining = self.state.inner.fifo.count_to_bytes(count);
+        let mut drained = 0;
+
+        loop {
+            let len = self.fifo_read_len(remaining, buffer.len())?;
+            if len == 0 {
+                break;
+            }
+            self.ll
+                .bus
+                .read_regs(FIFO_DATA_ADDR, &mut buffer[..len])
+                .map_err(Error::Bus)?;
+            f(&buffer[..len]);
+            remaining -= len;
+            drained += len;
+        }
+
+        Ok(drained)
+    }
+
+    /// Switch to another register bank
+    #[cfg(feature = "async")]
+    pub(crate) async fn async_select_bank(
+        &mut self,
+        bank: RegisterBank,
+    ) -> Result<(), Error<BUS::Error>>
+    where
+        BUS: AsyncRegisterInterface,
+    {
+        self.ll
+            .bus
+            .write_regs(REG_BANK_SEL_ADDR, &[bank])
+            .await
+            .map_err(Error::Bus)?;
+        self.ll.set_bank(bank);
+        Ok(())
+    }
+
+    /// Switch to another register bank
+    #[cfg(feature = "blocking")]
+    pub(crate) fn select_bank(&mut self, bank: RegisterBank) -> Result<(), Error<BUS::Error>>
+    where
+        BUS: RegisterInterface,
+    {
+        self.ll
+            .bus
+            .write_regs(REG_BANK_SEL_ADDR, &[bank])
+            .map_err(Error::Bus)?;
+        self.ll.set_bank(bank);
+        Ok(())
+    }
+
+    /// Switch the accelerometer to low power mode
+    ///
+    /// The accelerometer is clocked by the wake-up oscillator in low power
+    /// mode. Returns [`Error::UnsupportedOdr`] without touching the device if
+    /// the accel ODR is not available in low power mode.
+    #[cfg(feature = "async")]
+    pub(crate) async fn async_accel_low_power(&mut self) -> Result<(), Error<BUS::Error>>
+    where
+        BUS: AsyncRegisterInterface,
+    {
+        self.check_low_power_odr()?;
+        let mut bank0 = self.ll.try_bank::<0>()?;
+        bank0
+            .pwr_mgmt0()
+            .async_modify(|w| w.accel_mode(AccelMode::LowPower as u8))
+            .await?;
+        bank0
+            .intf_config1()
+            .async_modify(|w| w.accel_lp_clk_sel(0))
+            .await?;
+        Ok(())
+    }
+
+    /// Switch the accelerometer to low power mode
+    ///
+    /// The accelerometer is clocked by the wake-up oscillator in low power
+    /// mode. Returns [`Error::UnsupportedOdr`] without touching the device if
+    /// the accel ODR is not available in low power mode.
+    #[cfg(feature = "blocking")]
+    pub(crate) fn accel_low_power(&mut self) -> Result<(), Error<BUS::Error>>
+    where
+        BUS: RegisterInterface,
+    {
+        self.check_low_power_odr()?;
+        let mut bank0 = self.ll.try_bank::<0>()?;
+        bank0
+            .pwr_mgmt0()
+            .modify(|_, w| w.accel_mode(AccelMode::LowPower as u8))?;
+        bank0.intf_config1().modify(|_, w| w.accel_lp_clk_sel(0))?;
+        Ok(())
+    }
+
+    pub(crate) fn check_low_power_odr<E>(&self) -> Result<(), Error<E>> {
+        if self.state.inner.accel_odr.supports_low_power() {
+            Ok(())
+        } else {
+            Err(Error::UnsupportedOdr)
+        }
+    }
+
+    pub(crate) fn check_fifo_data_mode<E>(&self) -> Result<(), Error<E>> {
+        match self.state.inner.data_mode {
+            DataMode::FifoWatermark => Ok(()),
+            DataMode::DataReady => Err(Error::WrongDataMode),
+        }
+    }
+
+    #[cfg(feature = "async")]
+    pub(crate) fn check_data_ready_mode<E>(&self) -> Result<(), Error<E>> {
+        match self.state.inner.data_mode {
+            DataMode::DataReady => Ok(()),
+            DataMode::FifoWatermark => Err(Error::WrongDataMode),
+        }
+    }
+
+    /// Number of bytes to read so that only whole packets end up in the buffer
+    fn fifo_read_len<E>(&self, fifo_count: usize, buffer_len: usize) -> Result<usize, Error<E>> {
+        let packet_size = self.state.inner.fifo.packet_size();
+        if buffer_len < packet_size {
+            return Err(Error::BufferTooSmall);
+        }
+        Ok(fifo_count.min(buffer_len - buffer_len % packet_size))
+    }
+
+    fn fifo_read_result(int_status: u8, fifo_count: usize, data: &[u8]) -> FifoReadResult {
+        FifoReadResult {
+            int_status: InterruptStatus::from_registers([int_status, 0, 0]),
+            fifo_count,
+            bytes_read: data.len(),
+            packets: FifoParser::new(data).count(),
+            data_left: fifo_count > data.len(),
+        }
+    }
+
+    /// The gyroscope full-scale range set during initialization
+    pub fn gyro_full_scale(&self) -> GyroFullScale {
+        self.state.inner.gyro_full_scale
+    }
+
+    /// The accelerometer full-scale range set during initialization
+    pub fn accel_full_scale(&self) -> AccelFullScale {
+        self.state.inner.accel_full_scale
+    }
+
+    /// The full-scale ranges needed to convert the data to physical units
+    pub fn full_scale(&self) -> FullScale {
+        FullScale {
+            gyro: self.state.inner.gyro_full_scale,
+            accel: self.state.inner.accel_full_scale,
+        }
+    }
+
+    /// The FIFO settings set during initialization
+    pub fn fifo_config(&self) -> Fifo {
+        self.state.inner.fifo
+    }
+
+    /// A new [`TimestampTracker`] matching the timestamp settings
+    pub fn timestamp_tracker(&self) -> TimestampTracker {
+        self.state.inner.timestamp_tracker
     }
 
     /// Direct low level access to the underlying peripheral
-    pub fn ll(&mut self) -> &mut crate::ll::ICM42688<SPI> {
+    pub fn ll(&mut self) -> &mut crate::ll::ICM42688<BUS> {
         &mut self.ll
     }
 
-    pub fn release(self) -> SPI {
+    pub fn release(self) -> BUS {
         self.ll.release()
     }
 }
@@ -9,11 +9,13 @@
 
 use core::{fmt, marker::PhantomData};
 
-#[cfg(not(feature = "async"))]
-use embedded_hal::spi;
+use crate::interface::Interface;
 
 #[cfg(feature = "async")]
-use embedded_hal_async::spi as async_spi;
+use crate::interface::AsyncRegisterInterface;
+
+#[cfg(feature = "blocking")]
+use crate::interface::RegisterInterface;
 
 use paste::paste;
 
@@ -60,30 +62,61 @@ impl<'b, BUS, const BANK: RegisterBank> Registers<'b, BUS, BANK> {
         BANK
     }
 
+    /// Apply a list of register mutations.
+    ///
+    /// Every mutation is a read-modify-write of a single register.
+    #[cfg(feature = "blocking")]
+    pub fn apply_mutations(&mut self, mutations: &[Mutation<BANK>]) -> Result<(), Error<BUS>>
+    where
+        BUS: RegisterInterface,
+    {
+        for mutation in mutations {
+            let mut buf = [0; 1];
+            // Read
+            self.bus
+                .read_regs(mutation.register_id, &mut buf)
+                .map_err(Error::Transfer)?;
+
+            // Modify
+            buf[0] &= mutation.zero_mask;
+            buf[0] |= mutation.value;
+
+            // Write
+            self.bus
+                .write_regs(mutation.register_id, &buf)
+                .map_err(Error::Transfer)?;
+        }
+        Ok(())
+    }
+
     /// Apply a list of register mutations.
     ///
     /// This is mainly useful for reducing code size on async.
     #[cfg(feature = "async")]
-    pub async fn apply_mutations(&mut self, mutations: &[Mutation<BANK>]) -> Result<(), Error<BUS>>
+    pub async fn async_apply_mutations(
+        &mut self,
+        mutations: &[Mutation<BANK>],
+    ) -> Result<(), Error<BUS>>
     where
-        BUS: async_spi::SpiDevice<u8>,
+        BUS: AsyncRegisterInterface,
     {
         for mutation in mutations {
-            let mut buf = [0; 2];
+            let mut buf = [0; 1];
             // Read
-            init_header2(false, &mut buf, mutation.register_id);
             self.bus
-                .transfer_in_place(&mut buf)
+                .read_regs(mutation.register_id, &mut buf)
                 .await
                 .map_err(Error::Transfer)?;
 
             // Modify
-            buf[1] &= mutation.zero_mask;
-            buf[1] |= mutation.value;
+            buf[0] &= mutation.zero_mask;
+            buf[0] |= mutation.value;
 
             // Write
-            init_header2(true, &mut buf, mutation.register_id);
-            self.bus.write(&mut buf).await.map_err(Error::Transfer)?;
+            self.bus
+                .write_regs(mutation.register_id, &buf)
+                .await
+                .map_err(Error::Transfer)?;
         }
         Ok(())
     }
@@ -98,10 +131,10 @@ pub struct RegAccessor<'s, 'b, R, BUS, const BANK: RegisterBank>(
     PhantomData<R>,
 );
 
-#[cfg(not(feature = "async"))]
+#[cfg(feature = "blocking")]
 impl<R, BUS, const BANK: RegisterBank> RegAccessor<'_, '_, R, BUS, BANK>
 where
-    BUS: spi::SpiDevice<u8>,
+    BUS: RegisterInterface,
 {
     /// Read from the register
     pub fn read(&mut self) -> Result<R::Read, Error<BUS>>
@@ -111,10 +144,9 @@ where
         let mut r = R::read();
         let buffer = R::buffer(&mut r);
 
-        init_header::<R>(false, buffer);
         self.0
             .bus
-            .transfer_in_place(buffer)
+            .read_regs(R::ID, &mut buffer[HEADER_LEN..])
             .map_err(Error::Transfer)?;
 
         Ok(r)
@@ -130,9 +162,11 @@ where
         f(&mut w);
 
         let buffer = R::buffer(&mut w);
-        init_header::<R>(true, buffer);
 
-        BUS::write(self.0.bus, buffer).map_err(Error::Transfer)?;
+        self.0
+            .bus
+            .write_regs(R::ID, &buffer[HEADER_LEN..])
+            .map_err(Error::Transfer)?;
 
         Ok(())
     }
@@ -151,9 +185,11 @@ where
         f(&mut r, &mut w);
 
         let buffer = <R as Writable>::buffer(&mut w);
-        init_header::<R>(true, buffer);
 
-        BUS::write(self.0.bus, buffer).map_err(Error::Transfer)?;
+        self.0
+            .bus
+            .write_regs(R::ID, &buffer[HEADER_LEN..])
+            .map_err(Error::Transfer)?;
 
         Ok(())
     }
@@ -163,7 +199,7 @@ where
 #[cfg(feature = "async")]
 impl<R, BUS, const BANK: RegisterBank> RegAccessor<'_, '_, R, BUS, BANK>
 where
-    BUS: async_spi::SpiDevice<u8>,
+    BUS: AsyncRegisterInterface,
 {
     /// Read from the register
     pub async fn async_read(&mut self) -> Result<R::Read, Error<BUS>>
@@ -173,10 +209,11 @@ where
         let mut r = R::read();
         let buffer = R::buffer(&mut r);
 
-        init_header::<R>(false, buffer);
-        async_spi::SpiDevice::transfer_in_place(&mut self.0.bus, buffer)
+        self.0
+            .bus
+            .read_regs(R::ID, &mut buffer[HEADER_LEN..])
             .await
-            .map_err(|e| Error::Transfer(e))?;
+            .map_err(Error::Transfer)?;
 
         Ok(r)
     }
@@ -191,11 +228,12 @@ where
         f(&mut w);
 
         let buffer = R::buffer(&mut w);
-        let _ = init_header::<R>(true, buffer);
 
-        async_spi::SpiDevice::write(&mut self.0.bus, buffer)
+        self.0
+            .bus
+            .write_regs(R::ID, &buffer[HEADER_LEN..])
             .await
-            .map_err(|e| Error::Transfer(e))?;
+            .map_err(Error::Transfer)?;
 
         Ok(())
     }
@@ -214,9 +252,10 @@ where
         f(&mut w);
 
         let buffer = <R as Writable>::buffer(&mut w);
-        let _ = init_header::<R>(true, buffer);
 
-        async_spi::SpiDevice::write(&mut self.0.bus, buffer)
+        self.0
+            .bus
+            .write_regs(R::ID, &buffer[HEADER_LEN..])
             .await
             .map_err(Error::Transfer)?;
 
@@ -224,11 +263,7 @@ where
     }
 }
 
-#[cfg(feature = "async")]
-impl<R, BUS, const BANK: RegisterBank> RegAccessor<'_, '_, R, BUS, BANK>
-where
-    BUS: async_spi::SpiDevice<u8>,
-{
+impl<R, BUS, const BANK: RegisterBank> RegAccessor<'_, '_, R, BUS, BANK> {
     /// Create a mutation that can be applied later. Fields that aren't modified will remain
     /// untouched.
     pub fn mutation<F>(&mut self, mut f: F) -> Mutation<BANK>
@@ -262,46 +297,31 @@ pub struct Mutation<const BANK: RegisterBank> {
     value: u8,
 }
 
-/// An SPI error that can occur when communicating with the LSM6DSO
-#[cfg(not(feature = "async"))]
-pub enum Error<SPI>
-where
-    SPI: spi::SpiDevice<u8>,
-{
-    /// SPI error occured during a transfer transaction
-    Transfer(SPI::Error),
-}
-
-// We can't derive this implementation, as the compiler will complain that the
-// associated error type doesn't implement `Debug`.
-#[cfg(not(feature = "async"))]
-impl<SPI> fmt::Debug for Error<SPI>
-where
-    SPI: spi::SpiDevice<u8>,
-    SPI::Error: fmt::Debug,
-{
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        match self {
-            Error::Transfer(error) => write!(f, "Transfer({:?})", error),
+impl<const BANK: RegisterBank> Mutation<BANK> {
+    /// Set (`set == true`) or clear the bits in `mask` of register `register_id`
+    pub(crate) fn bits(register_id: u8, mask: u8, set: bool) -> Self {
+        Mutation {
+            register_id,
+            zero_mask: !mask,
+            value: if set { mask } else { 0 },
         }
     }
 }
 
-/// An SPI error that can occur when communicating with the LSM6DSO
-#[cfg(feature = "async")]
-pub enum Error<SPI>
+/// A bus error that can occur when communicating with the ICM426xx
+pub enum Error<BUS>
 where
-    SPI: async_spi::SpiDevice<u8>,
+    BUS: Interface,
 {
-    /// SPI error occured during a transfer transaction
-    Transfer(SPI::Error),
+    /// Bus error occured during a transfer transaction
+    Transfer(BUS::Error),
 }
 
-#[cfg(feature = "async")]
-impl<SPI> fmt::Debug for Error<SPI>
+// We can't derive this implementation, as the compiler will complain that the
+// associated error type doesn't implement `Debug`.
+impl<BUS> fmt::Debug for Error<BUS>
 where
-    SPI: async_spi::SpiDevice<u8>,
-    SPI::Error: fmt::Debug,
+    BUS: Interface,
 {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
@@ -310,20 +330,8 @@ where
     }
 }
 
-/// Initializes the SPI message header
-///
-/// Initializes the SPI message header for accessing a given register, writing
-/// the header directly into the provided buffer. Returns the length of the
-/// header that was written.
-fn init_header<R: Register>(write: bool, buffer: &mut [u8]) -> usize {
-    buffer[0] = (((!write as u8) << 7) & 0x80) | R::ID;
-
-    1
-}
-
-fn init_header2(write: bool, buffer: &mut [u8], id: u8) {
-    buffer[0] = (((!write as u8) << 7) & 0x80) | id;
-}
+/// Length of the header slot at the start of the register buffers
+const HEADER_LEN: usize = 1;
 
 /// Implemented for all registers
 ///
@@ -738,7 +746,7 @@ impl_register! {
         dmp_power_save, 7, 7, u8;  /// 0: DMP power save mode not active 1: DMP power save mode active (default)
     }
     0x57, 1, RW, SMD_CONFIG(smd_config) { /// SMD configuration register
-        smd_mode, 0, 1, u8;  /// 00: SMD disabled 01: Reserved 10: SMD short (1 sec wait) An SMD event is detected when two WOM are detected 1 sec apart 11: SMD long (3 sec wait) An SMD event is detected when two WOM are detected 3 sec apart
+        smd_mode, 0, 1, u8;  /// 00: SMD disabled 01: WOM mode 10: SMD short (1 sec wait) An SMD event is detected when two WOM are detected 1 sec apart 11: SMD long (3 sec wait) An SMD event is detected when two WOM are detected 3 sec apart
         wom_mode, 2, 2, u8;  /// 0: Initial sample is stored. Future samples are compared to initial sample 1: Compare current sample to previous sample
         wom_int_mode, 3, 3, u8;  /// 0: Set WoM interrupt on the OR of all enabled accelerometer thresholds 1: Set WoM interrupt on the AND of all enabled accelerometer threshold
         reserved_0, 4, 7, u8;  /// Reserved (0)
@@ -769,7 +777,7 @@ impl_register! {
     0x63, 1, RW, INT_CONFIG0(int_config0) { /// INT pin / interrupt configuration register
         fifo_full_int_clear, 0, 1, u8;  /// 00/01: Clear on status bit read; 10: Clear on FIFO 1 byte read; 11: Clear on both
         fifo_ths_int_clear, 2, 3, u8;  /// 00/01: Clear on status bit read; 10: Clear on FIFO 1 byte read; 11: Clear on both
-        ui_drdy_int_clear, 4, 5, u8;  /// 00/01: Clear on status bit read; 10: Clear on FIFO 1 byte read; 11: Clear on both
+        ui_drdy_int_clear, 4, 5, u8;  /// 00/01: Clear on status bit read; 10: Clear on sensor register read; 11: Clear on both
         reserved_0, 6, 7, u8;  /// Reserved (0)
     }
     0x64, 1, RW, INT_CONFIG1(int_config1) { /// INT pin / interrupt configuration register
@@ -787,6 +795,40 @@ impl_register! {
         ui_fsync_int1_en, 6, 6, u8;  /// Enable interrupt generation on UI FSYNC status
         reserved_0, 7, 7, u8;  /// Reserved (0)
     }
+    0x66, 1, RW, INT_SOURCE1(int_source1) { /// INT pin / interrupt source register
+        wom_x_int1_en, 0, 0, u8;  /// Enable interrupt generation on WOM X-axis status
+        wom_y_int1_en, 1, 1, u8;  /// Enable interrupt generation on WOM Y-axis status
+        wom_z_int1_en, 2, 2, u8;  /// Enable interrupt generation on WOM Z-axis status
+        smd_int1_en, 3, 3, u8;  /// Enable interrupt generation on SMD status
+        reserved_0, 4, 5, u8;  /// Reserved (0)
+        i3c_protocol_error_int1_en, 6, 6, u8;  /// Enable interrupt generation on I3C protocol error
+        reserved_1, 7, 7, u8;  /// Reserved (0)
+    }
+    0x68, 1, RW, INT_SOURCE3(int_source3) { /// INT pin / interrupt source register
+        ui_agc_rdy_int2_en, 0, 0, u8;  /// Enable interrupt generation on UI AGC ready status
+        fifo_full_int2_en, 1, 1, u8;  /// Enable interrupt generation on FIFO full status
+        fifo_ths_int2_en, 2, 2, u8;  /// Enable interrupt generation on FIFO threshold status
+        ui_drdy_int2_en, 3, 3, u8;  /// Enable interrupt generation on UI data ready status
+        reset_done_int2_en, 4, 4, u8;  /// Enable interrupt generation on reset done status
+        pll_rdy_int2_en, 5, 5, u8;  /// Enable interrupt generation on PLL ready status
+        ui_fsync_int2_en, 6, 6, u8;  /// Enable interrupt generation on UI FSYNC status
+        reserved_0, 7, 7, u8;  /// Reserved (0)
+    }
+    0x69, 1, RW, INT_SOURCE4(int_source4) { /// INT pin / interrupt source register
+        wom_x_int2_en, 0, 0, u8;  /// Enable interrupt generation on WOM X-axis status
+        wom_y_int2_en, 1, 1, u8;  /// Enable interrupt generation on WOM Y-axis status
+        wom_z_int2_en, 2, 2, u8;  /// Enable interrupt generation on WOM Z-axis status
+        smd_int2_en, 3, 3, u8;  /// Enable interrupt generation on SMD status
+        reserved_0, 4, 5, u8;  /// Reserved (0)
+        i3c_protocol_error_int2_en, 6, 6, u8;  /// Enable interrupt generation on I3C protocol error
+        reserved_1, 7, 7, u8;  /// Reserved (0)
+    }
+    0x6C, 1, RO, FIFO_LOST_PKT0(fifo_lost_pkt0) { /// FIFO lost packet count register
+        fifo_lost_pkt_cnt_7_0, 0, 7, u8;  /// Lower byte of the number of packets lost in the FIFO
+    }
+    0x6D, 1, RO, FIFO_LOST_PKT1(fifo_lost_pkt1) { /// FIFO lost packet count register
+        fifo_lost_pkt_cnt_15_8, 0, 7, u8;  /// Upper byte of the number of packets lost in the FIFO
+    }
     0x70, 1, RW, SELF_TEST_CONFIG(self_test_config) { /// Self-test configuration register
         en_gx_st, 0, 0, u8;  /// Enable gyroscope X-axis self-test (default 0, disabled)
         en_gy_st, 1, 1, u8;  /// Enable gyroscope Y-axis self-test (default 0, disabled)
@@ -808,6 +850,15 @@ impl_register! {
 
 impl_register! {
     BANK1,
+    0x03, 1, RW, SENSOR_CONFIG0(sensor_config0) { /// Sensor configuration register
+        xa_disable, 0, 0, u8;  /// 0: X accelerometer is on; 1: X accelerometer is disabled
+        ya_disable, 1, 1, u8;  /// 0: Y accelerometer is on; 1: Y accelerometer is disabled
+        za_disable, 2, 2, u8;  /// 0: Z accelerometer is on; 1: Z accelerometer is disabled
+        xg_disable, 3, 3, u8;  /// 0: X gyroscope is on; 1: X gyroscope is disabled
+        yg_disable, 4, 4, u8;  /// 0: Y gyroscope is on; 1: Y gyroscope is disabled
+        zg_disable, 5, 5, u8;  /// 0: Z gyroscope is on; 1: Z gyroscope is disabled
+        reserved_0, 6, 7, u8;  /// Reserved (0)
+    }
     0x0B, 1, RW, GYRO_CONFIG_STATIC2(gyro_config_static2) { /// Gyroscope static configuration register
         gyro_nf_dis, 0, 0, u8;  /// Gyroscope notch filter disable
         gyro_aaf_dis, 1, 1, u8;  /// Gyroscope anti-aliasing filter disable
@@ -824,15 +875,72 @@ impl_register! {
         gyro_aaf_deltsqr_11_8, 0, 3, u8;  /// Gyroscope anti-alias filter bandwidth selection, see Section 5.2 of the datasheet
         gyro_aaf_bitshift, 4, 7, u8;  /// Gyroscope anti-alias filter bandwidth selection, see Section 5.2 of the datasheet
     }
+    0x0F, 1, RW, GYRO_CONFIG_STATIC6(gyro_config_static6) { /// Gyroscope static configuration register
+        gyro_x_nf_coswz_7_0, 0, 7, u8;  /// Used for gyroscope X-axis notch filter frequency selection, see Section 5.1 of the datasheet
+    }
+    0x10, 1, RW, GYRO_CONFIG_STATIC7(gyro_config_static7) { /// Gyroscope static configuration register
+        gyro_y_nf_coswz_7_0, 0, 7, u8;  /// Used for gyroscope Y-axis notch filter frequency selection, see Section 5.1 of the datasheet
+    }
+    0x11, 1, RW, GYRO_CONFIG_STATIC8(gyro_config_static8) { /// Gyroscope static configuration register
+        gyro_z_nf_coswz_7_0, 0, 7, u8;  /// Used for gyroscope Z-axis notch filter frequency selection, see Section 5.1 of the datasheet
+    }
+    0x12, 1, RW, GYRO_CONFIG_STATIC9(gyro_config_static9) { /// Gyroscope static configuration register
+        gyro_x_nf_coswz_8, 0, 0, u8;  /// Used for gyroscope X-axis notch filter frequency selection, see Section 5.1 of the datasheet
+        gyro_y_nf_coswz_8, 1, 1, u8;  /// Used for gyroscope Y-axis notch filter frequency selection, see Section 5.1 of the datasheet
+        gyro_z_nf_coswz_8, 2, 2, u8;  /// Used for gyroscope Z-axis notch filter frequency selection, see Section 5.1 of the datasheet
+        gyro_x_nf_coswz_sel, 3, 3, u8;  /// Used for gyroscope X-axis notch filter frequency selection, see Section 5.1 of the datasheet
+        gyro_y_nf_coswz_sel, 4, 4, u8;  /// Used for gyroscope Y-axis notch filter frequency selection, see Section 5.1 of the datasheet
+        gyro_z_nf_coswz_sel, 5, 5, u8;  /// Used for gyroscope Z-axis notch filter frequency selection, see Section 5.1 of the datasheet
+        reserved_0, 6, 7, u8;  /// Reserved (0)
+    }
+    0x13, 1, RW, GYRO_CONFIG_STATIC10(gyro_config_static10) { /// Gyroscope static configuration register
+        reserved_0, 0, 3, u8;  /// Reserved (0)
+        gyro_nf_bw_sel, 4, 6, u8;  /// Selects bandwidth for gyroscope notch filter, see Section 5.1 of the datasheet
+        reserved_1, 7, 7, u8;  /// Reserved (0)
+    }
+    0x5F, 1, RO, XG_ST_DATA(xg_st_data) { /// X-gyro self test data
+        xg_st_data, 0, 7, u8;  /// X-gyro self test data
+    }
+    0x60, 1, RO, YG_ST_DATA(yg_st_data) { /// Y-gyro self test data
+        yg_st_data, 0, 7, u8;  /// Y-gyro self test data
+    }
+    0x61, 1, RO, ZG_ST_DATA(zg_st_data) { /// Z-gyro self test data
+        zg_st_data, 0, 7, u8;  /// Z-gyro self test data
+    }
+    0x62, 1, RO, TMSTVAL0(tmstval0) { /// Timestamp value register
+        tmst_value_7_0, 0, 7, u8;  /// Lower byte of the 20-bit timestamp latched by `TMST_STROBE`
+    }
+    0x63, 1, RO, TMSTVAL1(tmstval1) { /// Timestamp value register
+        tmst_value_15_8, 0, 7, u8;  /// Middle byte of the 20-bit timestamp latched by `TMST_STROBE`
+    }
+    0x64, 1, RO, TMSTVAL2(tmstval2) { /// Timestamp value register
+        tmst_value_19_16, 0, 3, u8;  /// Upper nibble of the 20-bit timestamp latched by `TMST_STROBE`
+        reserved_0, 4, 7, u8;  /// Reserved (0)
+    }
     0x76, 1, RW, REG_BANK_SEL(reg_bank_sel) { /// Register bank selection register
         bank_sel, 0, 2, u8;  /// Register bank selection
         reserved_0, 3, 7, u8;  /// Reserved (0)
     }
+    0x7A, 1, RW, INTF_CONFIG4(intf_config4) { /// Interface configuration register
+        reserved_0, 0, 0, u8;  /// Reserved (1)
+        spi_ap_4wire, 1, 1, u8;  /// 0: AP interface uses 3-wire SPI mode; 1: AP interface uses 4-wire SPI mode (default)
+        reserved_1, 2, 5, u8;  /// Reserved (0)
+        i3c_bus_mode, 6, 6, u8;  /// 0: Device is on a bus with I2C and I3C devices; 1: Device is on a bus with I3C devices only
+        reserved_2, 7, 7, u8;  /// Reserved (1)
+    }
     0x7B, 1, RW, INTF_CONFIG5(intf_config5) { /// Interface configuration register
         reserved_0, 0, 0, u8;  /// Reserved (0)
         pin9_function, 1, 2, u8;  /// Pin 9 function selection. 00: INT2; 01: FSYNC; 10: CLKIN, 11: Reserved
         reserved_1, 3, 7, u8;  /// Reserved (0)
     }
+    0x7C, 1, RW, INTF_CONFIG6(intf_config6) { /// Interface configuration register
+        i3c_sdr_en, 0, 0, u8;  /// 0: I3C SDR mode disabled; 1: I3C SDR mode enabled (default)
+        i3c_ibi_en, 1, 1, u8;  /// 0: I3C IBI disabled; 1: I3C IBI enabled
+        i3c_ibi_byte_en, 2, 2, u8;  /// 0: IBI payload byte disabled; 1: IBI payload byte enabled
+        i3c_en, 3, 3, u8;  /// 0: I3C disabled; 1: I3C enabled (default)
+        asynctime0_dis, 4, 4, u8;  /// 0: The ASYNC0 timing control is enabled; 1: disabled (default)
+        reserved_0, 5, 7, u8;  /// Reserved (010)
+    }
 }
 
 impl_register! {
@@ -849,10 +957,163 @@ impl_register! {
         accel_aaf_deltsqr_11_8, 0, 3, u8;  /// Control for accelerometer anti-alias filter bandwidth selection, see Section 5.2 of the datasheet
         accel_aaf_bitshift, 4, 7, u8;  /// Control for accelerometer anti-alias filter bandwidth selection, see Section 5.2 of the datasheet
     }
+    0x3B, 1, RO, XA_ST_DATA(xa_st_data) { /// X-accel self test data
+        xa_st_data, 0, 7, u8;  /// X-accel self test data
+    }
+    0x3C, 1, RO, YA_ST_DATA(ya_st_data) { /// Y-accel self test data
+        ya_st_data, 0, 7, u8;  /// Y-accel self test data
+    }
+    0x3D, 1, RO, ZA_ST_DATA(za_st_data) { /// Z-accel self test data
+        za_st_data, 0, 7, u8;  /// Z-accel self test data
+    }
+    0x76, 1, RW, REG_BANK_SEL(reg_bank_sel) { /// Register bank selection register
+        bank_sel, 0, 2, u8;  /// Register bank selection
+        reserved_0, 3, 7, u8;  /// Reserved (0)
+    }
+}
+
+impl_register! {
+    BANK3,
+    0x2A, 1, RW, CLKDIV(clkdiv) { /// Clock divider register
+        clkdiv, 0, 6, u8;  /// Clock divider value for the CLKIN/RTC clock
+        reserved_0, 7, 7, u8;  /// Reserved (0)
+    }
+    0x76, 1, RW, REG_BANK_SEL(reg_bank_sel) { /// Register bank selection register
+        bank_sel, 0, 2, u8;  /// Register bank selection
+        reserved_0, 3, 7, u8;  /// Reserved (0)
+    }
+}
+
+impl_register! {
+    BANK4,
+    0x40, 1, RW, APEX_CONFIG1(apex_config1) { /// APEX configuration register 1
+        dmp_power_save_time_sel, 0, 3, u8;  /// When the DMP is in power save mode, it is woken up if WOM detects motion for longer than this time. 0000: 0s; 0001: 4s; 0010: 8s (default); 0011: 12s; 0100: 16s; 0101: 20s; 0110: 24s; 0111: 28s; 1000: 32s; 1001: 36s; 1010: 40s; 1011: 44s; 1100: 48s; 1101: 52s; 1110: 56s; 1111: 60s
+        low_energy_amp_th_sel, 4, 7, u8;  /// Pedometer low energy mode amplitude threshold selection. Use default value 1010b
+    }
+    0x41, 1, RW, APEX_CONFIG2(apex_config2) { /// APEX configuration register 2
+        ped_step_cnt_th_sel, 0, 3, u8;  /// Minimum number of steps that must be detected before the pedometer step count begins incrementing (default 0101, 5 steps)
+        ped_amp_th_sel, 4, 7, u8;  /// Pedometer amplitude threshold selection. Use default value 1000b
+    }
+    0x42, 1, RW, APEX_CONFIG3(apex_config3) { /// APEX configuration register 3
+        ped_hi_en_th_sel, 0, 1, u8;  /// Threshold to select a valid step. Used to increase step detection for slow walk use case only. Use default value 01b
+        ped_sb_timer_th_sel, 2, 4, u8;  /// Duration of non-walk to exit the current walk mode, ped_step_cnt_th number of steps must again be detected before step count starts to increase (default 100, 150 samples = 3s at 50 Hz)
+        ped_step_det_th_sel, 5, 7, u8;  /// Minimum number of steps that must be detected before the pedometer step count begins incrementing, during walk mode (default 010, 2 steps)
+    }
+    0x43, 1, RW, APEX_CONFIG4(apex_config4) { /// APEX configuration register 4
+        reserved_0, 0, 2, u8;  /// Reserved (100)
+        sleep_time_out, 3, 5, u8;  /// Configures the time out for sleep detection, for Raise to Wake/Sleep feature 000: 1.28sec 001: 2.56sec 010: 3.84sec 011: 5.12sec 100: 6.40sec (default) 101: 7.68sec 110: 8.96sec 111: 10.24sec
+        tilt_wait_time_sel, 6, 7, u8;  /// Configures duration of delay after tilt is detected before interrupt is triggered 00: 0s 01: 2s 10: 4s (default) 11: 6s
+    }
+    0x44, 1, RW, APEX_CONFIG5(apex_config5) { /// APEX configuration register 5
+        mounting_matrix, 0, 2, u8;  /// Defines mounting matrix, chip to device frame 000: \[1 0 0; 0 1 0; 0 0 1\] (default) 001: \[1 0 0; 0 -1 0; 0 0 -1\] 010: \[-1 0 0; 0 1 0; 0 0 -1\] 011: \[-1 0 0; 0 -1 0; 0 0 1\] 100: \[0 1 0; 1 0 0; 0 0 -1\] 101: \[0 1 0; -1 0 0; 0 0 1\] 110: \[0 -1 0; 1 0 0; 0 0 1\] 111: \[0 -1 0; -1 0 0; 0 0 -1\]
+        reserved_0, 3, 7, u8;  /// Reserved (0)
+    }
+    0x45, 1, RW, APEX_CONFIG6(apex_config6) { /// APEX configuration register 6
+        sleep_gesture_delay, 0, 2, u8;  /// Configures detection window for sleep gesture detection 000: 0.32sec 001: 0.64sec 010: 0.96sec 011: 1.28sec 100: 1.60sec (default) 101: 1.92sec 110: 2.24sec 111: 2.56sec
+        reserved_0, 3, 7, u8;  /// Reserved (0)
+    }
+    0x46, 1, RW, APEX_CONFIG7(apex_config7) { /// APEX configuration register 7
+        tap_max_peak_tol, 0, 1, u8;  /// Maximum peak tolerance parameter. Use default value 01b
+        tap_min_jerk_thr, 2, 7, u8;  /// Tap detection minimum jerk threshold. Use default value 010001b
+    }
+    0x47, 1, RW, APEX_CONFIG8(apex_config8) { /// APEX configuration register 8
+        tap_tmin, 0, 2, u8;  /// Single tap window. Use default value 011b
+        tap_tavg, 3, 4, u8;  /// Energy measurement window size. Use default value 01b
+        tap_tmax, 5, 6, u8;  /// Tap measurement window. Use default value 01b
+        reserved_0, 7, 7, u8;  /// Reserved (0)
+    }
+    0x48, 1, RW, APEX_CONFIG9(apex_config9) { /// APEX configuration register 9
+        sensitivity_mode, 0, 0, u8;  /// 0: Low power mode at accelerometer ODR 25Hz; High performance mode at accelerometer ODR ≥ 50Hz 1: Slow walk mode at accelerometer ODR 25Hz and 50Hz
+        reserved_0, 1, 7, u8;  /// Reserved (0)
+    }
+    0x4A, 1, RW, ACCEL_WOM_X_THR(accel_wom_x_thr) { /// Wake on motion threshold register X
+        wom_x_th, 0, 7, u8;  /// Threshold value for the Wake on Motion Interrupt for X-axis accelerometer. WoM thresholds are expressed in fixed “mg” independent of the selected Range \[0g : 1g\]; Resolution 1g/256=~3.9mg
+    }
+    0x4B, 1, RW, ACCEL_WOM_Y_THR(accel_wom_y_thr) { /// Wake on motion threshold register Y
+        wom_y_th, 0, 7, u8;  /// Threshold value for the Wake on Motion Interrupt for Y-axis accelerometer. WoM thresholds are expressed in fixed “mg” independent of the selected Range \[0g : 1g\]; Resolution 1g/256=~3.9mg
+    }
+    0x4C, 1, RW, ACCEL_WOM_Z_THR(accel_wom_z_thr) { /// Wake on motion threshold register Z
+        wom_z_th, 0, 7, u8;  /// Threshold value for the Wake on Motion Interrupt for Z-axis accelerometer. WoM thresholds are expressed in fixed “mg” independent of the selected Range \[0g : 1g\]; Resolution 1g/256=~3.9mg
+    }
+    0x4D, 1, RW, INT_SOURCE6(int_source6) { /// INT pin / interrupt source register
+        tap_det_int1_en, 0, 0, u8;  /// Enable interrupt generation on tap detection
+        sleep_int1_en, 1, 1, u8;  /// Enable interrupt generation on sleep event
+        wake_int1_en, 2, 2, u8;  /// Enable interrupt generation on wake event
+        tilt_det_int1_en, 3, 3, u8;  /// Enable interrupt generation on tilt detection
+        step_cnt_ofl_int1_en, 4, 4, u8;  /// Enable interrupt generation on step count overflow
+        step_det_int1_en, 5, 5, u8;  /// Enable interrupt generation on step detection
+        reserved_0, 6, 7, u8;  /// Reserved (0)
+    }
+    0x4E, 1, RW, INT_SOURCE7(int_source7) { /// INT pin / interrupt source register
+        tap_det_int2_en, 0, 0, u8;  /// Enable interrupt generation on tap detection
+        sleep_int2_en, 1, 1, u8;  /// Enable interrupt generation on sleep event
+        wake_int2_en, 2, 2, u8;  /// Enable interrupt generation on wake event
+        tilt_det_int2_en, 3, 3, u8;  /// Enable interrupt generation on tilt detection
+        step_cnt_ofl_int2_en, 4, 4, u8;  /// Enable interrupt generation on step count overflow
+        step_det_int2_en, 5, 5, u8;  /// Enable interrupt generation on step detection
+        reserved_0, 6, 7, u8;  /// Reserved (0)
+    }
+    0x4F, 1, RW, INT_SOURCE8(int_source8) { /// I3C IBI source register
+        agc_rdy_ibi_en, 0, 0, u8;  /// Enable AGC ready interrupt to I3C IBI
+        fifo_full_ibi_en, 1, 1, u8;  /// Enable FIFO full interrupt to I3C IBI
+        fifo_ths_ibi_en, 2, 2, u8;  /// Enable FIFO threshold interrupt to I3C IBI
+        ui_drdy_ibi_en, 3, 3, u8;  /// Enable UI data ready interrupt to I3C IBI
+        pll_rdy_ibi_en, 4, 4, u8;  /// Enable PLL ready interrupt to I3C IBI
+        fsync_ibi_en, 5, 5, u8;  /// Enable UI FSYNC interrupt to I3C IBI
+        reserved_0, 6, 7, u8;  /// Reserved (0)
+    }
+    0x50, 1, RW, INT_SOURCE9(int_source9) { /// I3C IBI source register
+        reserved_0, 0, 0, u8;  /// Reserved (0)
+        wom_x_ibi_en, 1, 1, u8;  /// Enable WOM X interrupt to I3C IBI
+        wom_y_ibi_en, 2, 2, u8;  /// Enable WOM Y interrupt to I3C IBI
+        wom_z_ibi_en, 3, 3, u8;  /// Enable WOM Z interrupt to I3C IBI
+        smd_ibi_en, 4, 4, u8;  /// Enable SMD interrupt to I3C IBI
+        reserved_1, 5, 6, u8;  /// Reserved (0)
+        i3c_protocol_error_ibi_en, 7, 7, u8;  /// Enable I3C protocol error interrupt to I3C IBI
+    }
+    0x51, 1, RW, INT_SOURCE10(int_source10) { /// I3C IBI source register
+        tap_det_ibi_en, 0, 0, u8;  /// Enable tap detection interrupt to I3C IBI
+        sleep_det_ibi_en, 1, 1, u8;  /// Enable sleep event interrupt to I3C IBI
+        wake_det_ibi_en, 2, 2, u8;  /// Enable wake event interrupt to I3C IBI
+        tilt_det_ibi_en, 3, 3, u8;  /// Enable tilt detection interrupt to I3C IBI
+        step_cnt_ofl_ibi_en, 4, 4, u8;  /// Enable step count overflow interrupt to I3C IBI
+        step_det_ibi_en, 5, 5, u8;  /// Enable step detection interrupt to I3C IBI
+        reserved_0, 6, 7, u8;  /// Reserved (0)
+    }
     0x76, 1, RW, REG_BANK_SEL(reg_bank_sel) { /// Register bank selection register
         bank_sel, 0, 2, u8;  /// Register bank selection
         reserved_0, 3, 7, u8;  /// Reserved (0)
     }
+    0x77, 1, RW, OFFSET_USER0(offset_user0) { /// User offset register
+        gyro_x_offuser_7_0, 0, 7, u8;  /// Lower bits of X-gyro offset programmed by user. Max value is ±64 dps, resolution is 1/32 dps
+    }
+    0x78, 1, RW, OFFSET_USER1(offset_user1) { /// User offset register
+        gyro_x_offuser_11_8, 0, 3, u8;  /// Upper bits of X-gyro offset programmed by user. Max value is ±64 dps, resolution is 1/32 dps
+        gyro_y_offuser_11_8, 4, 7, u8;  /// Upper bits of Y-gyro offset programmed by user. Max value is ±64 dps, resolution is 1/32 dps
+    }
+    0x79, 1, RW, OFFSET_USER2(offset_user2) { /// User offset register
+        gyro_y_offuser_7_0, 0, 7, u8;  /// Lower bits of Y-gyro offset programmed by user. Max value is ±64 dps, resolution is 1/32 dps
+    }
+    0x7A, 1, RW, OFFSET_USER3(offset_user3) { /// User offset register
+        gyro_z_offuser_7_0, 0, 7, u8;  /// Lower bits of Z-gyro offset programmed by user. Max value is ±64 dps, resolution is 1/32 dps
+    }
+    0x7B, 1, RW, OFFSET_USER4(offset_user4) { /// User offset register
+        gyro_z_offuser_11_8, 0, 3, u8;  /// Upper bits of Z-gyro offset programmed by user. Max value is ±64 dps, resolution is 1/32 dps
+        accel_x_offuser_11_8, 4, 7, u8;  /// Upper bits of X-accel offset programmed by user. Max value is ±1 g, resolution is 0.5 mg
+    }
+    0x7C, 1, RW, OFFSET_USER5(offset_user5) { /// User offset register
+        accel_x_offuser_7_0, 0, 7, u8;  /// Lower bits of X-accel offset programmed by user. Max value is ±1 g, resolution is 0.5 mg
+    }
+    0x7D, 1, RW, OFFSET_USER6(offset_user6) { /// User offset register
+        accel_y_offuser_7_0, 0, 7, u8;  /// Lower bits of Y-accel offset programmed by user. Max value is ±1 g, resolution is 0.5 mg
+    }
+    0x7E, 1, RW, OFFSET_USER7(offset_user7) { /// User offset register
+        accel_y_offuser_11_8, 0, 3, u8;  /// Upper bits of Y-accel offset programmed by user. Max value is ±1 g, resolution is 0.5 mg
+        accel_z_offuser_11_8, 4, 7, u8;  /// Upper bits of Z-accel offset programmed by user. Max value is ±1 g, resolution is 0.5 mg
+    }
+    0x7F, 1, RW, OFFSET_USER8(offset_user8) { /// User offset register
+        accel_z_offuser_7_0, 0, 7, u8;  /// Lower bits of Z-accel offset programmed by user. Max value is ±1 g, resolution is 0.5 mg
+    }
 }
 
 /// Internal trait used by `impl_registers!`
@@ -0,0 +1,455 @@
+//! Conversion of raw sensor data to physical units
+//!
+//! [`Sample`] uses `f32` and SI units, [`FixedSample`] uses milli-units in
+//! `i32` for targets without an FPU. Both are created from a FIFO packet and the
+//! [`FullScale`] settings that were active when the data was sampled.
+
+use crate::{
+    config::{AccelFullScale, Endian, GyroFullScale},
+    fifo::{FifoPacket, FifoPacket1, FifoPacket2, FifoPacket3, FifoPacket4},
+};
+
+/// Standard gravity in m/s²
+const STANDARD_GRAVITY: f32 = 9.806_65;
+
+/// Value of a 16-bit sample when the sensor is off or the data is invalid
+const INVALID_16BIT: i32 = -32768;
+
+/// Value of a 20-bit sample when the sensor is off or the data is invalid
+const INVALID_20BIT: i32 = -524288;
+
+/// Accel sensitivity of the 20-bit FIFO data in LSB/g, times 10
+///
+/// The high resolution data always uses the ±16 g range.
+const ACCEL_20BIT_LSB_X10: i64 = 327_680;
+
+/// Gyro sensitivity of the 20-bit FIFO data in LSB/dps, times 10
+///
+/// The high resolution data always uses the ±2000 dps range.
+const GYRO_20BIT_LSB_X10: i64 = 2_624;
+
+/// Length of the sensor data registers, `TEMP_DATA1` to `GYRO_DATA_Z0`
+pub(crate) const DATA_REGISTERS_LEN: usize = 14;
+
+/// The full-scale ranges used to interpret the sensor data
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq)]
+pub struct FullScale {
+    pub gyro: GyroFullScale,
+    pub accel: AccelFullScale,
+}
+
+/// Format of the temperature in a FIFO packet
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum TemperatureFormat {
+    /// `raw / 2.07 + 25` °C, packets 1 to 3
+    Bits8,
+    /// `raw / 132.48 + 25` °C, packet 4
+    Bits16,
+}
+
+impl TemperatureFormat {
+    /// Sensitivity in LSB/°C, times 100
+    fn lsb_per_celsius_x100(self) -> i64 {
+        match self {
+            TemperatureFormat::Bits8 => 207,
+            TemperatureFormat::Bits16 => 13_248,
+        }
+    }
+}
+
+/// Raw sensor data of a packet with the sensitivities needed to scale it
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct RawSample {
+    accel: Option<[i32; 3]>,
+    gyro: Option<[i32; 3]>,
+    temperature: i32,
+    accel_lsb_x10: i64,
+    gyro_lsb_x10: i64,
+    temperature_format: TemperatureFormat,
+}
+
+impl RawSample {
+    pub(crate) fn from_packet(packet: &FifoPacket, full_scale: FullScale) -> Self {
+        match packet {
+            FifoPacket::Packet1(p) => Self::from_packet1(p, full_scale),
+            FifoPacket::Packet2(p) => Self::from_packet2(p, full_scale),
+            FifoPacket::Packet3(p) => Self::from_packet3(p, full_scale),
+            FifoPacket::Packet4(p) => Self::from_packet4(p),
+        }
+    }
+
+    /// Data of the sensor data registers `TEMP_DATA1` to `GYRO_DATA_Z0`
+    pub(crate) fn from_registers(
+        data: &[u8; DATA_REGISTERS_LEN],
+        endian: Endian,
+        full_scale: FullScale,
+    ) -> Self {
+        let value = |i: usize| {
+            let bytes = [data[i], data[i + 1]];
+            match endian {
+                Endian::Little => i16::from_le_bytes(bytes),
+                Endian::Big => i16::from_be_bytes(bytes),
+            }
+        };
+        let accel = [value(2), value(4), value(6)];
+        let gyro = [value(8), value(10), value(12)];
+        RawSample {
+            accel: valid(accel.map(i32::from), INVALID_16BIT),
+            gyro: valid(gyro.map(i32::from), INVALID_16BIT),
+            temperature: value(0) as i32,
+            accel_lsb_x10: full_scale.accel.lsb_per_g_x10(),
+            gyro_lsb_x10: full_scale.gyro.lsb_per_dps_x10(),
+            temperature_format: TemperatureFormat::Bits16,
+        }
+    }
+
+    /// Data of a packet 1, accel only
+    pub(crate) fn from_packet1(packet: &FifoPacket1, full_scale: FullScale) -> Self {
+        let accel = [
+            packet.accel_data_x(),
+            packet.accel_data_y(),
+            packet.accel_data_z(),
+        ];
+        RawSample {
+            accel: valid(accel.map(i32::from), INVALID_16BIT),
+            gyro: None,
+            temperature: packet.temperature_raw() as i8 as i32,
+            accel_lsb_x10: full_scale.accel.lsb_per_g_x10(),
+            gyro_lsb_x10: full_scale.gyro.lsb_per_dps_x10(),
+            temperature_format: TemperatureFormat::Bits8,
+        }
+    }
+
+    /// Data of a packet 2, gyro only
+    pub(crate) fn from_packet2(packet: &FifoPacket2, full_scale: FullScale) -> Self {
+        let gyro = [
+            packet.gyro_data_x(),
+            packet.gyro_data_y(),
+            packet.gyro_data_z(),
+        ];
+        RawSample {
+            accel: None,
+            gyro: valid(gyro.map(i32::from), INVALID_16BIT),
+            temperature: packet.temperature_raw() as i8 as i32,
+            accel_lsb_x10: full_scale.accel.lsb_per_g_x10(),
+            gyro_lsb_x10: full_scale.gyro.lsb_per_dps_x10(),
+            temperature_format: TemperatureFormat::Bits8,
+        }
+    }
+
+    /// Data of a packet 3, 16-bit accel and gyro
+    pub(crate) fn from_packet3(packet: &FifoPacket3, full_scale: FullScale) -> Self {
+        let accel = [
+            packet.accel_data_x(),
+            packet.accel_data_y(),
+            packet.accel_data_z(),
+        ];
+        let gyro = [
+            packet.gyro_data_x(),
+            packet.gyro_data_y(),
+            packet.gyro_data_z(),
+        ];
+        RawSample {
+            accel: valid(accel.map(i32::from), INVALID_16BIT),
+            gyro: valid(gyro.map(i32::from), INVALID_16BIT),
+            temperature: packet.temperature_raw() as i8 as i32,
+            accel_lsb_x10: full_scale.accel.lsb_per_g_x10(),
+            gyro_lsb_x10: full_scale.gyro.lsb_per_dps_x10(),
+            temperature_format: TemperatureFormat::Bits8,
+        }
+    }
+
+    /// Data of a packet 4, always 20-bit regardless of the full-scale settings
+    pub(crate) fn from_packet4(packet: &FifoPacket4) -> Self {
+        let accel = [
+            packet.accel_data_x(),
+            packet.accel_data_y(),
+            packet.accel_data_z(),
+        ];
+        let gyro = [
+            packet.gyro_data_x(),
+            packet.gyro_data_y(),
+            packet.gyro_data_z(),
+        ];
+        RawSample {
+            accel: valid(accel, INVALID_20BIT),
+            gyro: valid(gyro, INVALID_20BIT),
+            temperature: packet.temperature_raw() as i16 as i32,
+            accel_lsb_x10: ACCEL_20BIT_LSB_X10,
+            gyro_lsb_x10: GYRO_20BIT_LSB_X10,
+            temperature_format: TemperatureFormat::Bits16,
+        }
+    }
+}
+
+/// `None` if any of the axes holds the invalid marker
+fn valid(data: [i32; 3], invalid: i32) -> Option<[i32; 3]> {
+    if data.contains(&invalid) {
+        None
+    } else {
+        Some(data)
+    }
+}
+
+/// Sensor data in SI units
+#[derive(Clone, Copy, Debug, PartialEq, defmt::Format)]
+pub struct Sample {
+    /// Acceleration in m/s², `None` if the data is invalid (e.g. sensor off)
+    pub accel: Option<[f32; 3]>,
+    /// Angular rate in rad/s, `None` if the data is invalid (e.g. sensor off)
+    pub gyro: Option<[f32; 3]>,
+    /// Temperature in °C
+    pub temperature: f32,
+}
+
+impl Sample {
+    /// Convert a packet of any packet structure
+    pub fn from_packet(packet: &FifoPacket, full_scale: FullScale) -> Self {
+        Self::from_raw(&RawSample::from_packet(packet, full_scale))
+    }
+
+    /// Convert a packet 1 (16-bit accel data)
+    pub fn from_packet1(packet: &FifoPacket1, full_scale: FullScale) -> Self {
+        Self::from_raw(&RawSample::from_packet1(packet, full_scale))
+    }
+
+    /// Convert a packet 2 (16-bit gyro data)
+    pub fn from_packet2(packet: &FifoPacket2, full_scale: FullScale) -> Self {
+        Self::from_raw(&RawSample::from_packet2(packet, full_scale))
+    }
+
+    /// Convert a packet 3 (16-bit accel and gyro data)
+    pub fn from_packet3(packet: &FifoPacket3, full_scale: FullScale) -> Self {
+        Self::from_raw(&RawSample::from_packet3(packet, full_scale))
+    }
+
+    /// Convert a packet 4 (20-bit data)
+    ///
+    /// High resolution data always uses the ±16 g and ±2000 dps ranges.
+    pub fn from_packet4(packet: &FifoPacket4) -> Self {
+        Self::from_raw(&RawSample::from_packet4(packet))
+    }
+
+    /// Convert the content of the sensor data registers
+    pub fn from_registers(
+        data: &[u8; DATA_REGISTERS_LEN],
+        endian: Endian,
+        full_scale: FullScale,
+    ) -> Self {
+        Self::from_raw(&RawSample::from_registers(data, endian, full_scale))
+    }
+
+    pub(crate) fn from_raw(raw: &RawSample) -> Self {
+        let g_per_lsb = 10.0 / raw.accel_lsb_x10 as f32;
+        let dps_per_lsb = 10.0 / raw.gyro_lsb_x10 as f32;
+        let rad_per_lsb = dps_per_lsb * core::f32::consts::PI / 180.0;
+        let celsius_per_lsb = 100.0 / raw.temperature_format.lsb_per_celsius_x100() as f32;
+
+        Sample {
+            accel: raw
+                .accel
+                .map(|a| a.map(|v| v as f32 * g_per_lsb * STANDARD_GRAVITY)),
+            gyro: raw.gyro.map(|g| g.map(|v| v as f32 * rad_per_lsb)),
+            temperature: raw.temperature as f32 * celsius_per_lsb + 25.0,
+        }
+    }
+
+    /// Acceleration in g
+    pub fn accel_g(&self) -> Option<[f32; 3]> {
+        self.accel.map(|a| a.map(|v| v / STANDARD_GRAVITY))
+    }
+
+    /// Angular rate in dps
+    pub fn gyro_dps(&self) -> Option<[f32; 3]> {
+        self.gyro
+            .map(|g| g.map(|v| v * 180.0 / core::f32::consts::PI))
+    }
+}
+
+/// Sensor data in milli-units, for targets without an FPU
+#[derive(Clone, Copy, Debug, PartialEq, Eq, defmt::Format)]
+pub struct FixedSample {
+    /// Acceleration in mg, `None` if the data is invalid (e.g. sensor off)
+    pub accel: Option<[i32; 3]>,
+    /// Angular rate in mdps, `None` if the data is invalid (e.g. sensor off)
+    pub gyro: Option<[i32; 3]>,
+    /// Temperature in m°C
+    pub temperature: i32,
+}
+
+impl FixedSample {
+    /// Convert a packet of any packet structure
+    pub fn from_packet(packet: &FifoPacket, full_scale: FullScale) -> Self {
+        Self::from_raw(&RawSample::from_packet(packet, full_scale))
+    }
+
+    /// Convert a packet 1 (16-bit accel data)
+    pub fn from_packet1(packet: &FifoPacket1, full_scale: FullScale) -> Self {
+        Self::from_raw(&RawSample::from_packet1(packet, full_scale))
+    }
+
+    /// Convert a packet 2 (16-bit gyro data)
+    pub fn from_packet2(packet: &FifoPacket2, full_scale: FullScale) -> Self {
+        Self::from_raw(&RawSample::from_packet2(packet, full_scale))
+    }
+
+    /// Convert a packet 3 (16-bit accel and gyro data)
+    pub fn from_packet3(packet: &FifoPacket3, full_scale: FullScale) -> Self {
+        Self::from_raw(&RawSample::from_packet3(packet, full_scale))
+    }
+
+    /// Convert a packet 4 (20-bit data)
+    ///
+    /// High resolution data always uses the ±16 g and ±2000 dps ranges.
+    pub fn from_packet4(packet: &FifoPacket4) -> Self {
+        Self::from_raw(&RawSample::from_packet4(packet))
+    }
+
+    /// Convert the content of the sensor data registers
+    pub fn from_registers(
+        data: &[u8; DATA_REGISTERS_LEN],
+        endian: Endian,
+        full_scale: FullScale,
+    ) -> Self {
+        Self::from_raw(&RawSample::from_registers(data, endian, full_scale))
+    }
+
+    pub(crate) fn from_raw(raw: &RawSample) -> Self {
+        let scale = |v: i32, lsb_x10: i64| (v as i64 * 10_000 / lsb_x10) as i32;
+        let temperature_lsb_x100 = raw.temperature_format.lsb_per_celsius_x100();
+
+        FixedSample {
+            accel: raw.accel.map(|a| a.map(|v| scale(v, raw.accel_lsb_x10))),
+            gyro: raw.gyro.map(|g| g.map(|v| scale(v, raw.gyro_lsb_x10))),
+            temperature: (raw.temperature as i64 * 100_000 / temperature_lsb_x100) as i32 + 25_000,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn packet4(accel: [i32; 3], gyro: [i32; 3], temperature: i16) -> FifoPacket4 {
+        let hi = |v: i32| (v >> 12) as u8;
+        let lo = |v: i32| (v >> 4) as u8;
+        let ext = |a: i32, g: i32| ((a as u8 & 0xF) << 4) | (g as u8 & 0xF);
+        let [t1, t0] = temperature.to_be_bytes();
+        FifoPacket4 {
+            fifo_header: 0b0111_1000,
+            accel_data_x1: hi(accel[0]),
+            accel_data_x0: lo(accel[0]),
+            accel_data_y1: hi(accel[1]),
+            accel_data_y0: lo(accel[1]),
+            accel_data_z1: hi(accel[2]),
+            accel_data_z0: lo(accel[2]),
+            gyro_data_x1: hi(gyro[0]),
+            gyro_data_x0: lo(gyro[0]),
+            gyro_data_y1: hi(gyro[1]),
+            gyro_data_y0: lo(gyro[1]),
+            gyro_data_z1: hi(gyro[2]),
+            gyro_data_z0: lo(gyro[2]),
+            temp_data1: t1,
+            temp_data0: t0,
+            timestamp_h: 0,
+            timestamp_l: 0,
+            ext_accel_x_gyro_x: ext(accel[0], gyro[0]),
+            ext_accel_y_gyro_y: ext(accel[1], gyro[1]),
+            ext_accel_z_gyro_z: ext(accel[2], gyro[2]),
+        }
+    }
+
+    #[test]
+    fn test_packet4_fixed() {
+        // 1 g, -0.5 g, 0 g / 1000 dps, -2.5 dps, 0 dps / 35 °C
+        let packet = packet4([32768, -16384, 0], [262_400, -656, 0], 1325);
+        let sample = FixedSample::from_packet4(&packet);
+        assert_eq!(sample.accel, Some([1000, -500, 0]));
+        assert_eq!(sample.gyro, Some([1_000_000, -2500, 0]));
+        assert_eq!(sample.temperature, 35_001);
+    }
+
+    #[test]
+    fn test_packet4_float() {
+        let packet = packet4([32768, -16384, 0], [262_400, -656, 0], -3312);
+        let sample = Sample::from_packet4(&packet);
+        let accel = sample.accel_g().unwrap();
+        assert!((accel[0] - 1.0).abs() < 1e-6);
+        assert!((accel[1] + 0.5).abs() < 1e-6);
+        assert!((sample.accel.unwrap()[0] - STANDARD_GRAVITY).abs() < 1e-5);
+        let gyro = sample.gyro_dps().unwrap();
+        assert!((gyro[0] - 1000.0).abs() < 1e-2);
+        assert!((gyro[1] + 2.5).abs() < 1e-4);
+        assert!((sample.temperature - 0.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_packet4_invalid() {
+        let packet = packet4([INVALID_20BIT; 3], [1, 2, 3], 0);
+        let sample = FixedSample::from_packet4(&packet);
+        assert_eq!(sample.accel, None);
+        assert!(sample.gyro.is_some());
+    }
+
+    #[test]
+    fn test_packet3_full_scale() {
+        let mut packet = FifoPacket3::default();
+        // 1 g at ±2 g, 100 dps at ±250 dps, 35.1 °C
+        [packet.accel_data_x1, packet.accel_data_x0] = 16384i16.to_be_bytes();
+        [packet.gyro_data_x1, packet.gyro_data_x0] = 13100i16.to_be_bytes();
+        packet.temp_data0 = 21;
+        let full_scale = FullScale {
+            gyro: GyroFullScale::_250Dps,
+            accel: AccelFullScale::_2G,
+        };
+        let sample = FixedSample::from_packet3(&packet, full_scale);
+        assert_eq!(sample.accel, Some([1000, 0, 0]));
+        assert_eq!(sample.gyro, Some([100_000, 0, 0]));
+        assert_eq!(sample.temperature, 35_144);
+    }
+
+    #[test]
+    fn test_sensitivity_table() {
+        // The datasheet rounds the odd ranges, 16.4 * 32 would be 524.8
+        assert_eq!(GyroFullScale::_62_5Dps.sensitivity(), 524.3);
+        assert_eq!(GyroFullScale::_250Dps.lsb_per_dps_x10(), 1310);
+        assert_eq!(AccelFullScale::_2G.sensitivity(), 16384.0);
+    }
+
+    #[test]
+    fn test_packet1_invalid_16bit() {
+        let mut packet = FifoPacket1::default();
+        [packet.accel_data_y1, packet.accel_data_y0] = i16::MIN.to_be_bytes();
+        let sample = FixedSample::from_packet1(&packet, FullScale::default());
+        assert_eq!(sample.accel, None);
+        assert_eq!(sample.gyro, None);
+        assert_eq!(sample.temperature, 25_000);
+    }
+
+    #[test]
+    fn test_packet2_gyro() {
+        let mut packet = FifoPacket2::default();
+        // -164 LSB is -10 dps at ±2000 dps
+        [packet.gyro_data_y1, packet.gyro_data_y0] = (-164i16).to_be_bytes();
+        packet.temp_data0 = -20i8 as u8;
+        let sample = FixedSample::from_packet2(&packet, FullScale::default());
+        assert_eq!(sample.accel, None);
+        assert_eq!(sample.gyro, Some([0, -10_000, 0]));
+        assert_eq!(sample.temperature, 15_339);
+    }
+
+    #[test]
+    fn test_registers_endian() {
+        // 25 °C, accel X 1 g at ±16 g, gyro Z invalid
+        let big = [0, 0, 0x08, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x80, 0];
+        let sample = FixedSample::from_registers(&big, Endian::Big, FullScale::default());
+        assert_eq!(sample.accel, Some([1000, 0, 0]));
+        assert_eq!(sample.gyro, None);
+        assert_eq!(sample.temperature, 25_000);
+
+        let little = [0, 0, 0, 0x08, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x80];
+        let sample = FixedSample::from_registers(&little, Endian::Little, FullScale::default());
+        assert_eq!(sample.accel, Some([1000, 0, 0]));
+        assert_eq!(sample.gyro, None);
+    }
+}
@@ -0,0 +1,171 @@
+//! Reconstruction of FIFO timestamps
+//!
+//! The FIFO only holds 16 bits of timestamp per packet, either the time since
+//! the previous ODR or a wrapping counter. [`TimestampTracker`] turns them
+//! into a monotonic 64-bit timeline in µs.
+
+use crate::{
+    config::{Pin9Function, TimestampResolution},
+    fifo::{FifoPacket, HEADER_FSYNC_TIMESTAMP, HEADER_ODR_TIMESTAMP},
+    Config,
+};
+
+/// Turns the 16-bit FIFO timestamps into a monotonic µs timeline
+///
+/// Packets have to be passed in the order they were read from the FIFO. Call
+/// [`TimestampTracker::reset`] after the FIFO was flushed or has overflowed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, defmt::Format)]
+pub struct TimestampTracker {
+    delta: bool,
+    /// Length of one tick in µs is `tick_num / tick_den`
+    tick_num: u64,
+    tick_den: u64,
+    last_raw: Option<u16>,
+    /// Ticks between the two latest ODR timestamps
+    last_interval: Option<u16>,
+    ticks: u64,
+}
+
+impl TimestampTracker {
+    /// Create a tracker matching the timestamp settings of `config`
+    pub fn new(config: &Config) -> Self {
+        let (tick_num, tick_den) = match config.timestamp.resolution {
+            TimestampResolution::Fine => (1, 1),
+            TimestampResolution::Coarse if config.pin9.function == Pin9Function::CLKIN => {
+                (1_000_000, config.pin9.clkin_frequency as u64)
+            }
+            TimestampResolution::Coarse => (16, 1),
+        };
+        TimestampTracker {
+            delta: config.timestamp.delta,
+            tick_num,
+            tick_den,
+            last_raw: None,
+            last_interval: None,
+            ticks: 0,
+        }
+    }
+
+    /// Forget the previous timestamps, the timeline continues where it was
+    pub fn reset(&mut self) {
+        self.last_raw = None;
+        self.last_interval = None;
+    }
+
+    /// Time of the latest sample in µs
+    pub fn now(&self) -> u64 {
+        self.ticks_to_us(self.ticks)
+    }
+
+    fn ticks_to_us(&self, ticks: u64) -> u64 {
+        ticks * self.tick_num / self.tick_den
+    }
+
+    /// Add a raw 16-bit timestamp and return the time of its sample in µs
+    pub fn update(&mut self, raw: u16) -> u64 {
+        let elapsed = if self.delta {
+            Some(raw)
+        } else {
+            // Nothing to compare the first counter value with
+            self.last_raw.map(|last| raw.wrapping_sub(last))
+        };
+        self.last_raw = Some(raw);
+        self.last_interval = elapsed;
+        self.ticks += elapsed.unwrap_or(0) as u64;
+        self.now()
+    }
+
+    /// Add the timestamp of a FIFO packet
+    ///
+    /// Returns the time of the sample in µs, or `None` if the packet does not
+    /// carry a timestamp (packets 1 and 2).
+    ///
+    /// Packets tagged with an FSYNC delay don't hold their ODR timestamp, their
+    /// time is extrapolated from the previous ODR interval.
+    pub fn process(&mut self, packet: &FifoPacket) -> Option<u64> {
+        let raw = packet.timestamp()?;
+        match packet.fifo_header().has_timestamp_fsync().value() {
+            HEADER_ODR_TIMESTAMP => Some(self.update(raw)),
+            HEADER_FSYNC_TIMESTAMP => {
+                let interval = self.last_interval?;
+                self.last_raw = self.last_raw.map(|last| last.wrapping_add(interval));
+                self.ticks += interval as u64;
+                Some(self.now())
+            }
+            _ => None,
+        }
+    }
+
+    /// Time of the FSYNC pulse tagged on `packet` in µs
+    ///
+    /// Has to be called after `packet` was passed to
+    /// [`TimestampTracker::process`].
+    pub fn fsync_time(&self, packet: &FifoPacket) -> Option<u64> {
+        let delay = packet.fsync_delay()?;
+        Some(self.now().saturating_sub(self.ticks_to_us(delay as u64)))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::fifo::FifoPacket4;
+
+    #[test]
+    fn test_delta() {
+        let mut tracker = TimestampTracker::new(&Config::default());
+        // 1 kHz ODR at 16 µs resolution
+        assert_eq!(tracker.update(62), 992);
+        assert_eq!(tracker.update(63), 2000);
+        assert_eq!(tracker.update(62), 2992);
+    }
+
+    #[test]
+    fn test_absolute_wraparound() {
+        let mut config = Config::default();
+        config.timestamp.delta = false;
+        config.timestamp.resolution = TimestampResolution::Fine;
+        let mut tracker = TimestampTracker::new(&config);
+        assert_eq!(tracker.update(65_000), 0);
+        assert_eq!(tracker.update(65_500), 500);
+        assert_eq!(tracker.update(464), 1000);
+        tracker.reset();
+        assert_eq!(tracker.update(10), 1000);
+        assert_eq!(tracker.update(20), 1010);
+    }
+
+    #[test]
+    fn test_clkin() {
+        let mut config = Config::default();
+        config.pin9.function = Pin9Function::CLKIN;
+        let mut tracker = TimestampTracker::new(&config);
+        // 32768 ticks are one second
+        for _ in 0..32 {
+            tracker.update(1024);
+        }
+        assert_eq!(tracker.now(), 1_000_000);
+    }
+
+    #[test]
+    fn test_process() {
+        let mut tracker = TimestampTracker::new(&Config::default());
+        let mut packet = FifoPacket4 {
+            fifo_header: 0b0111_1000,
+            timestamp_l: 10,
+            ..Default::default()
+        };
+        assert_eq!(tracker.process(&FifoPacket::Packet4(packet)), Some(160));
+
+        // FSYNC pulse 3 ticks before the ODR, the ODR interval is extrapolated
+        packet.fifo_header = 0b0111_1100;
+        packet.timestamp_l = 3;
+        let packet = FifoPacket::Packet4(packet);
+        assert_eq!(tracker.process(&packet), Some(320));
+        assert_eq!(tracker.fsync_time(&packet), Some(272));
+
+        // No timestamp in packets 1 and 2
+        let packet = FifoPacket::Packet1(Default::default());
+        assert_eq!(tracker.process(&packet), None);
+        assert_eq!(tracker.now(), 320);
+    }
+}
@@ -1,40 +1,40 @@
 // Indicates that the `ICM42688` instance is not initialized yet
 
-#[cfg(feature = "async")]
-use embedded_hal_async::delay::DelayNs;
-
-#[cfg(not(feature = "async"))]
-use embedded_hal::delay::DelayNs;
+use crate::{
+    config::{DataMode, FifoMode, Pin9Function},
+    interface::{Interface, InterfaceKind},
+    register_bank::{Mutation, Registers, BANK0, BANK1, BANK2},
+    Config, Error, Ready, Uninitialized, ICM42688,
+};
 
-use crate::{Config, Ready, Uninitialized, ICM42688};
+#[cfg(feature = "async")]
+use crate::interface::AsyncRegisterInterface;
 
-#[derive(Debug, defmt::Format, Copy, Clone)]
-pub struct InitializationError;
+#[cfg(feature = "blocking")]
+use crate::interface::RegisterInterface;
 
-impl<SPI> ICM42688<SPI, Uninitialized> {
-    /// Create a new instance of `DW3000`
+impl<BUS> ICM42688<BUS, Uninitialized> {
+    /// Create a new instance of `ICM42688`
     ///
-    /// Requires the SPI peripheral and the chip select pin that are connected
-    /// to the DW3000.
-    pub fn new(spi: SPI) -> Self {
+    /// Requires the bus the device is connected to, either an `SpiDevice` or
+    /// an [`I2cInterface`](crate::interface::I2cInterface).
+    pub fn new(bus: BUS) -> Self {
         ICM42688 {
-            ll: crate::ll::ICM42688::new(spi),
-            _state: Uninitialized,
+            ll: crate::ll::ICM42688::new(bus),
+            state: Uninitialized,
         }
     }
 
     #[cfg(feature = "async")]
-    pub async fn initialize(
+    pub async fn async_initialize(
         mut self,
-        mut delay: impl DelayNs,
+        mut delay: impl embedded_hal_async::delay::DelayNs,
         config: Config,
-    ) -> Result<ICM42688<SPI, Ready>, InitializationError>
+    ) -> Result<ICM42688<BUS, Ready>, Error<BUS::Error>>
     where
-        SPI: embedded_hal_async::spi::SpiDevice,
+        BUS: AsyncRegisterInterface,
     {
-        use crate::{config::Pin9Function, Ready};
-
-        let mut bank0 = self.ll.bank::<0>();
+        let mut bank0 = self.ll.try_bank::<0>()?;
 
         // Soft reset the device
         //
@@ -42,351 +42,294 @@ impl<SPI> ICM42688<SPI, Uninitialized> {
         bank0
             .device_config()
             .async_modify(|w| w.soft_reset_config(1))
-            .await
-            .unwrap();
+            .await?;
 
         // Wait 1ms for the device to reset
         delay.delay_ms(1).await;
 
         // Read the WHO_AM_I register to verify the device is present
-        let who_am_i = bank0.who_am_i().async_read().await.unwrap().value();
+        let who_am_i = bank0.who_am_i().async_read().await?.value();
         if who_am_i != 0x47 {
-            return Err(InitializationError);
+            return Err(Error::WrongChipId(who_am_i));
         }
 
-        let bank0_mutations = [
-            bank0.int_config().mutation(|w| {
-                w.int1_mode(1)
-                    .int1_drive_circuit(config.int1.drive as u8)
-                    .int1_polarity(config.int1.polarity as u8)
-            }),
-            bank0.fifo_config().mutation(|w| w.fifo_mode(11)),
-            bank0
-                .intf_config0()
-                .mutation(|w| w.fifo_count_endian(1).sensor_data_endian(1).ui_sifs_cfg(11)),
-            bank0.intf_config1().mutation(|w| {
-                w.afsr(0b01); // Disable AFSR (undocumented adaptive scale change)
-                if config.pin9.function == Pin9Function::CLKIN {
-                    w.rtc_mode(1);
-                }
-                w
-            }),
-            bank0
-                .gyro_config0()
-                .mutation(|w| w.gyro_fs_sel(0b000).gyro_odr(config.gyro.odr as u8)),
-            bank0
-                .accel_config0()
-                .mutation(|w| w.accel_fs_sel(0b000).accel_odr(config.accel.odr as u8)),
-            bank0.gyro_config1().mutation(|w| w.gyro_ui_filt_ord(0b0)),
-            bank0
-                .gyro_accel_config0()
-                .mutation(|w| w.accel_ui_filt_bw(0b00).gyro_ui_filt_bw(0b00)),
-            bank0.accel_config1().mutation(|w| w.accel_ui_filt_ord(0b0)),
-            bank0.tmst_config().mutation(|w| {
-                w.tmst_en(1)
-                    .tmst_delta_en(1)
-                    .tmst_to_regs_en(1)
-                    .tmst_res(1)
-                    .tmst_fsync_en(0)
-            }),
-            bank0.fifo_config1().mutation(|w| {
-                w.fifo_wm_gt_th(1)
-                    .fifo_hires_en(1)
-                    .fifo_temp_en(1)
-                    .fifo_gyro_en(1)
-                    .fifo_accel_en(1)
-                    .fifo_tmst_fsync_en(0)
-            }),
-            bank0
-                .fifo_config2()
-                .mutation(|w| w.fifo_wm_7_0(config.fifo_watermark.to_le_bytes()[0])),
-            bank0
-                .fifo_config3()
-                .mutation(|w| w.fifo_wm_11_8(config.fifo_watermark.to_le_bytes()[1] & 0b1111)),
-            bank0.int_config0().mutation(|w| w.fifo_ths_int_clear(0b10)),
-            bank0.int_config1().mutation(|w| w.int_async_reset(0)),
-            bank0.int_source0().mutation(|w| w.fifo_ths_int1_en(1)),
-        ];
-
-        bank0.apply_mutations(&bank0_mutations).await.unwrap();
-
+        let bank0_mutations = Self::bank0_mutations(&mut bank0, &config);
+        bank0.async_apply_mutations(&bank0_mutations).await?;
+        let fsync_mutation = Self::fsync_mutation(&mut bank0, &config);
         bank0
-            .reg_bank_sel()
-            .async_write(|r| r.bank_sel(1))
-            .await
-            .unwrap();
+            .async_apply_mutations(fsync_mutation.as_slice())
+            .await?;
+
+        bank0.reg_bank_sel().async_write(|r| r.bank_sel(1)).await?;
 
         self.ll.set_bank(1);
 
         // Set bank 1
-        let mut bank1 = self.ll.bank::<1>();
+        let mut bank1 = self.ll.try_bank::<1>()?;
 
-        let bank1_mutations = [
-            bank1
-                .gyro_config_static2()
-                .mutation(|w| w.gyro_nf_dis(0).gyro_aaf_dis(0)),
-            bank1
-                .gyro_config_static3()
-                .mutation(|w| w.gyro_aaf_delt(13)),
-            bank1
-                .gyro_config_static4()
-                .mutation(|w| w.gyro_aaf_deltsqr_7_0(170)),
-            bank1
-                .gyro_config_static5()
-                .mutation(|w| w.gyro_aaf_deltsqr_11_8(0).gyro_aaf_bitshift(8)),
-            bank1
-                .intf_config5()
-                .mutation(|w| w.pin9_function(config.pin9.function as u8)),
-        ];
-        bank1.apply_mutations(&bank1_mutations).await.unwrap();
+        let bank1_mutations = Self::bank1_mutations(&mut bank1, &config);
+        bank1.async_apply_mutations(&bank1_mutations).await?;
 
-        bank1
-            .reg_bank_sel()
-            .async_write(|r| r.bank_sel(2))
-            .await
-            .unwrap();
+        bank1.reg_bank_sel().async_write(|r| r.bank_sel(2)).await?;
 
         self.ll.set_bank(2);
 
         // Set bank 2
-        let mut bank2 = self.ll.bank::<2>();
+        let mut bank2 = self.ll.try_bank::<2>()?;
 
-        let bank2_mutations = [
-            bank2
-                .accel_config_static2()
-                .mutation(|w| w.accel_aaf_dis(0).accel_aaf_delt(13)),
-            bank2
-                .accel_config_static3()
-                .mutation(|w| w.accel_aaf_deltsqr_7_0(170)),
-            bank2
-                .accel_config_static4()
-                .mutation(|w| w.accel_aaf_deltsqr_11_8(0).accel_aaf_bitshift(8)),
-        ];
-        bank2.apply_mutations(&bank2_mutations).await.unwrap();
+        let bank2_mutations = Self::bank2_mutations(&mut bank2);
+        bank2.async_apply_mutations(&bank2_mutations).await?;
 
         // Set bank 0
-        bank2
-            .reg_bank_sel()
-            .async_write(|r| r.bank_sel(0))
-            .await
-            .unwrap();
+        bank2.reg_bank_sel().async_write(|r| r.bank_sel(0)).await?;
 
         self.ll.set_bank(0);
 
         // Only enable gyro and accel when all registers are written
         // Refer to Section 12.9 of the datasheet
         self.ll
-            .bank::<0>()
+            .try_bank::<0>()?
             .pwr_mgmt0()
             .async_modify(|w| w.gyro_mode(0b11).accel_mode(config.accel.mode as u8))
-            .await
-            .unwrap();
+            .await?;
 
         // Delay for 200us per the datasheet after writing to PWR_MGMT0
         delay.delay_us(200).await;
 
         Ok(ICM42688 {
             ll: self.ll,
-            _state: Ready,
+            state: Ready::new(&config),
         })
     }
 
-    #[cfg(not(feature = "async"))]
+    #[cfg(feature = "blocking")]
     pub fn initialize(
         mut self,
-        mut delay: impl DelayNs,
+        mut delay: impl embedded_hal::delay::DelayNs,
         config: Config,
-    ) -> Result<ICM42688<SPI, Ready>, InitializationError>
+    ) -> Result<ICM42688<BUS, Ready>, Error<BUS::Error>>
     where
-        SPI: embedded_hal::spi::SpiDevice,
+        BUS: RegisterInterface,
     {
-        use crate::{config::Pin9Function, Ready};
-
-        let mut bank0 = self.ll.bank::<0>();
+        let mut bank0 = self.ll.try_bank::<0>()?;
 
         // Soft reset the device
         //
         // This is required to ensure the device is in a known state
         bank0
             .device_config()
-            .modify(|_, w| w.soft_reset_config(1))
-            .unwrap();
+            .modify(|_, w| w.soft_reset_config(1))?;
 
         // Wait 1ms for the device to reset
         delay.delay_ms(1);
 
         // Read the WHO_AM_I register to verify the device is present
-        let who_am_i = bank0.who_am_i().read().unwrap().value();
+        let who_am_i = bank0.who_am_i().read()?.value();
         if who_am_i != 0x47 {
-            return Err(InitializationError);
+            return Err(Error::WrongChipId(who_am_i));
         }
 
-        bank0
-            .int_config()
-            .modify(|_, w| {
-                w.int1_mode(1)
-                    .int1_drive_circuit(config.int1.drive as u8)
-                    .int1_polarity(config.int1.polarity as u8)
-            })
-            .unwrap();
-        bank0.fifo_config().modify(|_, w| w.fifo_mode(11)).unwrap();
-        bank0
-            .intf_config0()
-            .modify(|_, w| w.fifo_count_endian(1).sensor_data_endian(1).ui_sifs_cfg(11))
-            .unwrap();
-        bank0
-            .intf_config1()
-            .modify(|_, w| {
-                w.afsr(0b01); // Disable AFSR (undocumented adaptive scale change)
-                if config.pin9.function == Pin9Function::CLKIN {
-                    w.rtc_mode(1);
-                }
-                w
-            })
-            .unwrap();
-
-        bank0
-            .gyro_config0()
-            .modify(|_, w| w.gyro_fs_sel(0b000).gyro_odr(config.gyro.odr as u8))
-            .unwrap();
-
-        bank0
-            .accel_config0()
-            .modify(|_, w| w.accel_fs_sel(0b000).accel_odr(config.accel.odr as u8))
-            .unwrap();
-
-        bank0
-            .gyro_config1()
-            .modify(|_, w| w.gyro_ui_filt_ord(0b0))
-            .unwrap();
-
-        bank0
-            .gyro_accel_config0()
-            .modify(|_, w| w.accel_ui_filt_bw(0b00).gyro_ui_filt_bw(0b00))
-            .unwrap();
-
-        bank0
-            .accel_config1()
-            .modify(|_, w| w.accel_ui_filt_ord(0b0))
-            .unwrap();
-        bank0
-            .tmst_config()
-            .modify(|_, w| {
-                w.tmst_en(1)
-                    .tmst_delta_en(1)
-                    .tmst_to_regs_en(1)
-                    .tmst_res(1)
-                    .tmst_fsync_en(0)
-            })
-            .unwrap();
-        bank0
-            .fifo_config1()
-            .modify(|_, w| {
-                w.fifo_wm_gt_th(1)
-                    .fifo_hires_en(1)
-                    .fifo_temp_en(1)
-                    .fifo_gyro_en(1)
-                    .fifo_accel_en(1)
-                    .fifo_tmst_fsync_en(0)
-            })
-            .unwrap();
-        bank0
-            .fifo_config2()
-            .modify(|_, w| w.fifo_wm_7_0(config.fifo_watermark.to_le_bytes()[0]))
-            .unwrap();
-        bank0
-            .fifo_config3()
-            .modify(|_, w| w.fifo_wm_11_8(config.fifo_watermark.to_le_bytes()[1] & 0b1111))
-            .unwrap();
-        bank0
-            .int_config0()
-            .modify(|_, w| w.fifo_ths_int_clear(0b10))
-            .unwrap();
-        bank0
-            .int_config1()
-            .modify(|_, w| w.int_async_reset(0))
-            .unwrap();
-        bank0
-            .int_source0()
-            .modify(|_, w| w.fifo_ths_int1_en(1))
-            .unwrap();
+        let bank0_mutations = Self::bank0_mutations(&mut bank0, &config);
+        bank0.apply_mutations(&bank0_mutations)?;
+        let fsync_mutation = Self::fsync_mutation(&mut bank0, &config);
+        bank0.apply_mutations(fsync_mutation.as_slice())?;
 
-        bank0.reg_bank_sel().write(|r| r.bank_sel(1)).unwrap();
+        bank0.reg_bank_sel().write(|r| r.bank_sel(1))?;
 
         self.ll.set_bank(1);
 
         // Set bank 1
-        let mut bank1 = self.ll.bank::<1>();
-
-        bank1
-            .gyro_config_static2()
-            .modify(|_, w| w.gyro_nf_dis(0).gyro_aaf_dis(0))
-            .unwrap();
+        let mut bank1 = self.ll.try_bank::<1>()?;
 
-        bank1
-            .gyro_config_static3()
-            .modify(|_, w| w.gyro_aaf_delt(13))
-            .unwrap();
+        let bank1_mutations = Self::bank1_mutations(&mut bank1, &config);
+        bank1.apply_mutations(&bank1_mutations)?;
 
-        bank1
-            .gyro_config_static4()
-            .modify(|_, w| w.gyro_aaf_deltsqr_7_0(170))
-            .unwrap();
-
-        bank1
-            .gyro_config_static5()
-            .modify(|_, w| w.gyro_aaf_deltsqr_11_8(0).gyro_aaf_bitshift(8))
-            .unwrap();
-
-        bank1
-            .intf_config5()
-            .modify(|_, w| w.pin9_function(config.pin9.function as u8))
-            .unwrap();
-
-        bank1.reg_bank_sel().write(|r| r.bank_sel(2)).unwrap();
+        bank1.reg_bank_sel().write(|r| r.bank_sel(2))?;
 
         self.ll.set_bank(2);
 
         // Set bank 2
-        let mut bank2 = self.ll.bank::<2>();
+        let mut bank2 = self.ll.try_bank::<2>()?;
 
-        bank2
-            .accel_config_static2()
-            .modify(|_, w| w.accel_aaf_dis(0).accel_aaf_delt(13))
-            .unwrap();
-
-        bank2
-            .accel_config_static3()
-            .modify(|_, w| w.accel_aaf_deltsqr_7_0(170))
-            .unwrap();
-
-        bank2
-            .accel_config_static4()
-            .modify(|_, w| w.accel_aaf_deltsqr_11_8(0).accel_aaf_bitshift(8))
-            .unwrap();
+        let bank2_mutations = Self::bank2_mutations(&mut bank2);
+        bank2.apply_mutations(&bank2_mutations)?;
 
         // Set bank 0
-        bank2.reg_bank_sel().write(|r| r.bank_sel(0)).unwrap();
+        bank2.reg_bank_sel().write(|r| r.bank_sel(0))?;
 
         self.ll.set_bank(0);
 
+        // Only enable gyro and accel when all registers are written
+        // Refer to Section 12.9 of the datasheet
         self.ll
-            .bank::<0>()
+            .try_bank::<0>()?
             .pwr_mgmt0()
-            .modify(|_, w| w.gyro_mode(0b11).accel_mode(config.accel.mode as u8))
-            .unwrap();
+            .modify(|_, w| w.gyro_mode(0b11).accel_mode(config.accel.mode as u8))?;
 
         // Delay for 200us per the datasheet after writing to PWR_MGMT0
         delay.delay_us(200);
 
         Ok(ICM42688 {
             ll: self.ll,
-            _state: Ready,
+            state: Ready::new(&config),
+        })
+    }
+
+    /// Bank 0 configuration applied by `initialize`
+    fn bank0_mutations(
+        bank0: &mut Registers<'_, BUS, BANK0>,
+        config: &Config,
+    ) -> [Mutation<BANK0>; 16]
+    where
+        BUS: Interface,
+    {
+        [
+            bank0.int_config().mutation(|w| {
+                w.int1_mode(config.int1.mode as u8)
+                    .int1_drive_circuit(config.int1.drive as u8)
+                    .int1_polarity(config.int1.polarity as u8)
+                    .int2_mode(config.int2.mode as u8)
+                    .int2_drive_circuit(config.int2.drive as u8)
+                    .int2_polarity(config.int2.polarity as u8)
+            }),
+            bank0.fifo_config().mutation(|w| match config.data_mode {
+                DataMode::FifoWatermark => w.fifo_mode(config.fifo.mode as u8),
+                DataMode::DataReady => w.fifo_mode(FifoMode::Bypass as u8),
+            }),
+            bank0.intf_config0().mutation(|w| {
+                w.fifo_count_rec(config.fifo.count_records as u8)
+                    .fifo_count_endian(1)
+                    .sensor_data_endian(1)
+                    .ui_sifs_cfg(Self::ui_sifs_cfg())
+            }),
+            bank0.intf_config1().mutation(|w| {
+                w.afsr(0b01); // Disable AFSR (undocumented adaptive scale change)
+                if config.pin9.function == Pin9Function::CLKIN {
+                    w.rtc_mode(1);
+                }
+                w
+            }),
+            bank0.gyro_config0().mutation(|w| {
+                w.gyro_fs_sel(config.gyro.full_scale as u8)
+                    .gyro_odr(config.gyro.odr as u8)
+            }),
+            bank0.accel_config0().mutation(|w| {
+                w.accel_fs_sel(config.accel.full_scale as u8)
+                    .accel_odr(config.accel.odr as u8)
+            }),
+            bank0.gyro_config1().mutation(|w| w.gyro_ui_filt_ord(0b0)),
+            bank0
+                .gyro_accel_config0()
+                .mutation(|w| w.accel_ui_filt_bw(0b00).gyro_ui_filt_bw(0b00)),
+            bank0.accel_config1().mutation(|w| w.accel_ui_filt_ord(0b0)),
+            bank0.tmst_config().mutation(|w| {
+                w.tmst_en(1)
+                    .tmst_delta_en(config.timestamp.delta as u8)
+                    .tmst_to_regs_en(1)
+                    .tmst_res(config.timestamp.resolution as u8)
+                    .tmst_fsync_en(Self::fsync_enabled(config) as u8)
+            }),
+            bank0.fifo_config1().mutation(|w| {
+                w.fifo_resume_partial_rd(1) // Allow draining the FIFO in chunks
+                    .fifo_wm_gt_th(1)
+                    .fifo_hires_en(config.fifo.high_resolution as u8)
+                    .fifo_temp_en(1)
+                    .fifo_gyro_en(config.fifo.gyro as u8)
+                    .fifo_accel_en(config.fifo.accel as u8)
+                    .fifo_tmst_fsync_en(Self::fsync_enabled(config) as u8)
+            }),
+            bank0
+                .fifo_config2()
+                .mutation(|w| w.fifo_wm_7_0(config.fifo_watermark.to_le_bytes()[0])),
+            bank0
+                .fifo_config3()
+                .mutation(|w| w.fifo_wm_11_8(config.fifo_watermark.to_le_bytes()[1] & 0b1111)),
+            bank0.int_config0().mutation(|w| match config.data_mode {
+                // Clear on FIFO read
+                DataMode::FifoWatermark => w.fifo_ths_int_clear(0b10),
+                // Clear on sensor register read
+                DataMode::DataReady => w.ui_drdy_int_clear(0b10),
+            }),
+            bank0.int_config1().mutation(|w| w.int_async_reset(0)),
+            bank0.int_source0().mutation(|w| match config.data_mode {
+                DataMode::FifoWatermark => w.fifo_ths_int1_en(1),
+                DataMode::DataReady => w.ui_drdy_int1_en(1),
+            }),
+        ]
+    }
+
+    /// FSYNC configuration, only applied if pin 9 is used as FSYNC
+    fn fsync_mutation(
+        bank0: &mut Registers<'_, BUS, BANK0>,
+        config: &Config,
+    ) -> Option<Mutation<BANK0>>
+    where
+        BUS: Interface,
+    {
+        Self::fsync_enabled(config).then(|| {
+            bank0.fsync_config().mutation(|w| {
+                w.fsync_polarity(config.fsync.polarity as u8)
+                    .fsync_ui_sel(config.fsync.tag as u8)
+            })
         })
     }
 
+    fn fsync_enabled(config: &Config) -> bool {
+        config.pin9.function == Pin9Function::FSYNC
+    }
+
+    /// Bank 1 configuration applied by `initialize`
+    fn bank1_mutations(
+        bank1: &mut Registers<'_, BUS, BANK1>,
+        config: &Config,
+    ) -> [Mutation<BANK1>; 5] {
+        [
+            bank1
+                .gyro_config_static2()
+                .mutation(|w| w.gyro_nf_dis(0).gyro_aaf_dis(0)),
+            bank1
+                .gyro_config_static3()
+                .mutation(|w| w.gyro_aaf_delt(13)),
+            bank1
+                .gyro_config_static4()
+                .mutation(|w| w.gyro_aaf_deltsqr_7_0(170)),
+            bank1
+                .gyro_config_static5()
+                .mutation(|w| w.gyro_aaf_deltsqr_11_8(0).gyro_aaf_bitshift(8)),
+            bank1
+                .intf_config5()
+                .mutation(|w| w.pin9_function(config.pin9.function as u8)),
+        ]
+    }
+
+    /// Bank 2 configuration applied by `initialize`
+    fn bank2_mutations(bank2: &mut Registers<'_, BUS, BANK2>) -> [Mutation<BANK2>; 3] {
+        [
+            bank2
+                .accel_config_static2()
+                .mutation(|w| w.accel_aaf_dis(0).accel_aaf_delt(13)),
+            bank2
+                .accel_config_static3()
+                .mutation(|w| w.accel_aaf_deltsqr_7_0(170)),
+            bank2
+                .accel_config_static4()
+                .mutation(|w| w.accel_aaf_deltsqr_11_8(0).accel_aaf_bitshift(8)),
+        ]
+    }
+
+    /// Disable the serial interface that is not in use
+    fn ui_sifs_cfg() -> u8
+    where
+        BUS: Interface,
+    {
+        match BUS::KIND {
+            InterfaceKind::Spi => 0b11, // Disable I2C
+            InterfaceKind::I2c => 0b10, // Disable SPI
+        }
+    }
+
     /// Direct low level access to the underlying peripheral
-    pub fn ll(&mut self) -> &mut crate::ll::ICM42688<SPI> {
+    pub fn ll(&mut self) -> &mut crate::ll::ICM42688<BUS> {
         &mut self.ll
     }
 }